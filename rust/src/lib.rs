@@ -35,6 +35,21 @@ pub struct JjResult {
 struct BranchInfo {
     name: String,
     is_local: bool,
+    /// None for a local bookmark entry; Some(remote name) for a remote-tracking entry.
+    remote: Option<String>,
+    /// Hex commit ids the ref resolves to. More than one means the ref is conflicted/diverged.
+    target_commit_ids: Vec<String>,
+    is_conflicted: bool,
+    /// Only meaningful for remote entries: whether this remote ref is tracked by a local bookmark.
+    is_tracked: bool,
+    /// Only meaningful for tracked remote entries: the remote target is a
+    /// proper ancestor of the local one. False when either side is conflicted
+    /// or the two have diverged.
+    is_ahead: bool,
+    /// Only meaningful for tracked remote entries: the local target is a
+    /// proper ancestor of the remote one. False when either side is conflicted
+    /// or the two have diverged.
+    is_behind: bool,
 }
 
 /// Workspace information for serialization
@@ -50,7 +65,10 @@ struct WorkspaceInfo {
 #[derive(Serialize)]
 struct FileChangeInfo {
     path: String,
-    status: String, // "modified", "added", "deleted"
+    status: String, // "modified", "added", "deleted", "renamed"
+    has_conflict: bool,
+    /// Set when `status` is "renamed": the path this file was renamed from.
+    old_path: Option<String>,
 }
 
 /// File contents for before/after comparison
@@ -59,6 +77,25 @@ struct FileContents {
     before: String,
     after: String,
     path: String,
+    is_binary: bool,
+    has_conflict: bool,
+}
+
+/// A single line within a `DiffHunk`.
+#[derive(Serialize)]
+struct DiffHunkLine {
+    kind: String, // "context", "added", "removed"
+    content: String,
+}
+
+/// One `@@ -before_start,before_len +after_start,after_len @@` hunk.
+#[derive(Serialize)]
+struct DiffHunk {
+    before_start: usize,
+    before_len: usize,
+    after_start: usize,
+    after_len: usize,
+    lines: Vec<DiffHunkLine>,
 }
 
 /// Operation information for serialization
@@ -68,6 +105,13 @@ struct OperationInfo {
     description: String,
     timestamp: String,
     is_current: bool,
+    parent_ids: Vec<String>,
+    /// Heads present in this operation's view but not its parent's; 0 unless
+    /// the operation has exactly one parent.
+    commits_added: usize,
+    /// Heads present in the parent's view but not this operation's; 0 unless
+    /// the operation has exactly one parent.
+    commits_removed: usize,
 }
 
 /// Revision information for serialization
@@ -241,6 +285,97 @@ pub extern "C" fn jj_open_repo(path: *const c_char) -> *mut RepoHandle {
     }
 }
 
+/// Open a jj repository at the given path, loaded as of a past operation
+/// instead of the current head. All read-only FFI calls (branches,
+/// workspaces, working-copy changes) then see that historical snapshot.
+/// Returns NULL on error (check stderr)
+#[no_mangle]
+pub extern "C" fn jj_open_repo_at_op(path: *const c_char, op_id: *const c_char) -> *mut RepoHandle {
+    let path_str = unsafe {
+        if path.is_null() {
+            eprintln!("jj_open_repo_at_op: null path");
+            return ptr::null_mut();
+        }
+        match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("jj_open_repo_at_op: invalid path UTF-8: {}", e);
+                return ptr::null_mut();
+            }
+        }
+    };
+
+    let op_id_str = unsafe {
+        if op_id.is_null() {
+            eprintln!("jj_open_repo_at_op: null op_id");
+            return ptr::null_mut();
+        }
+        match CStr::from_ptr(op_id).to_str() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("jj_open_repo_at_op: invalid op_id UTF-8: {}", e);
+                return ptr::null_mut();
+            }
+        }
+    };
+
+    let path = Path::new(path_str);
+
+    let settings = match create_user_settings() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("jj_open_repo_at_op: failed to create settings: {}", e);
+            return ptr::null_mut();
+        }
+    };
+
+    let working_copy_factories = default_working_copy_factories();
+    let workspace = match Workspace::load(&settings, path, &Default::default(), &working_copy_factories) {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            eprintln!("jj_open_repo_at_op: failed to load workspace: {:?}", e);
+            return ptr::null_mut();
+        }
+    };
+
+    let workspace_name = workspace.workspace_name().as_str().to_string();
+    let workspace_root = workspace.workspace_root().to_string_lossy().to_string();
+    let repo_loader = workspace.repo_loader();
+
+    // Loading at head first gives us an op store and a head operation to
+    // walk backward from when resolving the (possibly short) op id prefix.
+    let head_repo = match repo_loader.load_at_head() {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("jj_open_repo_at_op: failed to load repo at head: {:?}", e);
+            return ptr::null_mut();
+        }
+    };
+
+    let target_op = match find_operation_from(head_repo.op_store(), head_repo.operation(), op_id_str) {
+        Ok(op) => op,
+        Err(e) => {
+            eprintln!("jj_open_repo_at_op: {}", e);
+            return ptr::null_mut();
+        }
+    };
+
+    match repo_loader.load_at(&target_op) {
+        Ok(repo) => {
+            let handle = Box::new(RepoHandle {
+                repo,
+                current_workspace: workspace_name,
+                repo_root: workspace_root,
+            });
+            Box::into_raw(handle)
+        }
+        Err(e) => {
+            eprintln!("jj_open_repo_at_op: failed to load repo at operation {}: {:?}", op_id_str, e);
+            ptr::null_mut()
+        }
+    }
+}
+
 /// List branches in the repository
 /// Returns JjResult with JSON array of branch names on success
 #[no_mangle]
@@ -255,10 +390,46 @@ pub extern "C" fn jj_list_branches(handle: *mut RepoHandle) -> JjResult {
     let mut branches = Vec::new();
 
     // Get local branches (bookmarks in jj terminology) from the view
-    for (name, _target) in handle.repo.view().local_bookmarks() {
+    for (name, target) in handle.repo.view().local_bookmarks() {
         branches.push(BranchInfo {
             name: name.as_str().to_string(),
             is_local: true,
+            remote: None,
+            target_commit_ids: target.added_ids().map(|id| id.hex()).collect(),
+            is_conflicted: target.added_ids().count() > 1,
+            is_tracked: false,
+            is_ahead: false,
+            is_behind: false,
+        });
+    }
+
+    // Remote-tracking bookmarks, including ones that only exist on a remote
+    // and haven't been fetched into a local bookmark at all.
+    for (symbol, remote_ref) in handle.repo.view().all_remote_bookmarks() {
+        let is_tracked = remote_ref.is_tracked();
+
+        let (is_ahead, is_behind) = if is_tracked {
+            let local_target = handle.repo.view().get_local_bookmark(symbol.name);
+            match (local_target.as_normal(), remote_ref.target.as_normal()) {
+                (Some(local_id), Some(remote_id)) if local_id != remote_id => (
+                    is_ancestor_via_index(&handle.repo, remote_id, local_id),
+                    is_ancestor_via_index(&handle.repo, local_id, remote_id),
+                ),
+                _ => (false, false),
+            }
+        } else {
+            (false, false)
+        };
+
+        branches.push(BranchInfo {
+            name: symbol.name.as_str().to_string(),
+            is_local: false,
+            remote: Some(symbol.remote.as_str().to_string()),
+            target_commit_ids: remote_ref.target.added_ids().map(|id| id.hex()).collect(),
+            is_conflicted: remote_ref.target.added_ids().count() > 1,
+            is_tracked,
+            is_ahead,
+            is_behind,
         });
     }
 
@@ -336,55 +507,78 @@ fn get_current_wc_parent_ids(handle: &RepoHandle) -> Result<Vec<jj_lib::backend:
     Ok(wc_commit.parent_ids().to_vec())
 }
 
-/// Resolve revision spec strings (commit ID prefixes) to commit IDs.
-/// The search is limited to MAX_REVISION_SEARCH_DEPTH commits to avoid
-/// unbounded walks in very large repositories.
-const MAX_REVISION_SEARCH_DEPTH: usize = 10000;
+/// Build the parse context jj's revset grammar needs to turn a string like
+/// `@-` or `description(glob:"wip*")` into a `RevsetExpression`.
+fn revset_parse_context<'a>(
+    handle: &'a RepoHandle,
+    aliases_map: &'a jj_lib::revset::RevsetAliasesMap,
+    extensions: &'a jj_lib::revset::RevsetExtensions,
+) -> jj_lib::revset::RevsetParseContext<'a> {
+    use jj_lib::ref_name::WorkspaceName;
+    use jj_lib::revset::{RevsetParseContext, RevsetWorkspaceContext};
+
+    let workspace_ctx = RevsetWorkspaceContext {
+        path_converter: &jj_lib::repo_path::RepoPathUiConverter::Fs {
+            cwd: Path::new(&handle.repo_root).to_path_buf(),
+            base: Path::new(&handle.repo_root).to_path_buf(),
+        },
+        workspace_name: WorkspaceName::new(&handle.current_workspace),
+    };
 
-fn resolve_revision_specs(handle: &RepoHandle, specs: &[String]) -> Result<Vec<jj_lib::backend::CommitId>, String> {
-    use std::collections::HashSet;
+    RevsetParseContext::new(
+        aliases_map,
+        "jjazy@localhost",
+        None,
+        extensions,
+        Some(workspace_ctx),
+    )
+}
 
-    let mut result = Vec::new();
+/// Parse and evaluate a revset expression against the repository.
+/// `@` resolves to the current workspace's working-copy commit, `@-` to its
+/// parent; bookmarks, tags, git refs, and change-id/commit-id prefixes all
+/// resolve through jj's own symbol resolver.
+fn evaluate_revset(handle: &RepoHandle, revset_str: &str) -> Result<Vec<jj_lib::backend::CommitId>, String> {
+    use jj_lib::revset::{DefaultSymbolResolver, RevsetAliasesMap, RevsetExpression, RevsetExtensions};
 
-    for spec in specs {
-        // Walk from working copy commits to find matching revision
-        let mut found: Option<jj_lib::backend::CommitId> = None;
-        let mut visited: HashSet<String> = HashSet::new();
-        let mut to_visit: Vec<jj_lib::backend::CommitId> = Vec::new();
+    let aliases_map = RevsetAliasesMap::new();
+    let extensions = RevsetExtensions::default();
+    let parse_context = revset_parse_context(handle, &aliases_map, &extensions);
 
-        for (_ws_id, commit_id) in handle.repo.view().wc_commit_ids() {
-            to_visit.push(commit_id.clone());
-        }
+    let expression = RevsetExpression::parse(revset_str, &parse_context)
+        .map_err(|e| format!("Failed to parse revset '{}': {}", revset_str, e))?;
 
-        while let Some(commit_id) = to_visit.pop() {
-            // Depth limit to avoid unbounded walks in large repos
-            if visited.len() >= MAX_REVISION_SEARCH_DEPTH {
-                break;
-            }
+    let symbol_resolver = DefaultSymbolResolver::new(handle.repo.as_ref(), extensions.symbol_resolvers());
+    let resolved = expression
+        .resolve_user_expression(handle.repo.as_ref(), &symbol_resolver)
+        .map_err(|e| format!("Failed to resolve revset '{}': {}", revset_str, e))?;
 
-            let hex = commit_id.hex();
-            if visited.contains(&hex) {
-                continue;
-            }
-            visited.insert(hex.clone());
+    let revset = resolved
+        .evaluate(handle.repo.as_ref())
+        .map_err(|e| format!("Failed to evaluate revset '{}': {}", revset_str, e))?;
 
-            if hex.starts_with(spec) {
-                found = Some(commit_id);
-                break;
-            }
+    revset
+        .iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error walking revset '{}': {}", revset_str, e))
+}
 
-            if let Ok(c) = handle.repo.store().get_commit(&commit_id) {
-                for parent_id in c.parent_ids() {
-                    if !visited.contains(&parent_id.hex()) {
-                        to_visit.push(parent_id.clone());
-                    }
-                }
-            }
-        }
+/// Resolve revision spec strings (revset expressions, not just hex prefixes)
+/// to commit IDs. Each spec must resolve to exactly one commit.
+fn resolve_revision_specs(handle: &RepoHandle, specs: &[String]) -> Result<Vec<jj_lib::backend::CommitId>, String> {
+    let mut result = Vec::new();
 
-        match found {
-            Some(id) => result.push(id),
-            None => return Err(format!("Revision not found: {}", spec)),
+    for spec in specs {
+        let commit_ids = evaluate_revset(handle, spec)?;
+        match commit_ids.len() {
+            0 => return Err(format!("Revision not found: {}", spec)),
+            1 => result.push(commit_ids[0].clone()),
+            n => {
+                return Err(format!(
+                    "Revision spec '{}' is ambiguous: resolved to {} commits",
+                    spec, n
+                ))
+            }
         }
     }
 
@@ -658,10 +852,36 @@ pub extern "C" fn jj_workspace_forget(
     }
 }
 
-/// Get file changes in the current working copy
-/// Returns JjResult with JSON array of file change info on success
+/// Load the `Workspace` for `ws_name`: the current workspace lives at
+/// `handle.repo_root`, other workspaces follow the sibling-directory
+/// convention used by `jj_list_workspaces`/`jj_workspace_add`.
+fn load_named_workspace(
+    handle: &RepoHandle,
+    ws_name: &str,
+    settings: &UserSettings,
+) -> Result<Workspace, String> {
+    let ws_root = if ws_name == handle.current_workspace {
+        Path::new(&handle.repo_root).to_path_buf()
+    } else {
+        Path::new(&handle.repo_root)
+            .parent()
+            .map(|parent| parent.join(ws_name))
+            .ok_or_else(|| "Cannot locate sibling workspace directory".to_string())?
+    };
+
+    let working_copy_factories = default_working_copy_factories();
+    Workspace::load(settings, &ws_root, &Default::default(), &working_copy_factories)
+        .map_err(|e| format!("Failed to load workspace {}: {:?}", ws_name, e))
+}
+
+/// Check whether a workspace's working copy is stale relative to the repo
+/// (e.g. its recorded operation was garbage-collected), a common failure
+/// mode for multi-workspace setups once the op log gets pruned.
+/// Returns JjResult with a JSON boolean on success.
 #[no_mangle]
-pub extern "C" fn jj_get_working_copy_changes(handle: *mut RepoHandle) -> JjResult {
+pub extern "C" fn jj_workspace_is_stale(handle: *mut RepoHandle, workspace_name: *const c_char) -> JjResult {
+    use jj_lib::working_copy::{check_stale_working_copy, WorkingCopyFreshness};
+
     let handle = unsafe {
         if handle.is_null() {
             return JjResult::error("null repo handle".to_string());
@@ -669,6 +889,272 @@ pub extern "C" fn jj_get_working_copy_changes(handle: *mut RepoHandle) -> JjResu
         &*handle
     };
 
+    let ws_name = unsafe {
+        if workspace_name.is_null() {
+            return JjResult::error("null workspace_name".to_string());
+        }
+        match CStr::from_ptr(workspace_name).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid workspace_name UTF-8: {}", e)),
+        }
+    };
+
+    let wc_commit_id = match handle
+        .repo
+        .view()
+        .wc_commit_ids()
+        .iter()
+        .find(|(ws_id, _)| ws_id.as_str() == ws_name)
+        .map(|(_, commit_id)| commit_id.clone())
+    {
+        Some(id) => id,
+        None => return JjResult::error(format!("Workspace not found: {}", ws_name)),
+    };
+
+    let wc_commit = match handle.repo.store().get_commit(&wc_commit_id) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(format!("Failed to get working copy commit: {}", e)),
+    };
+
+    let settings = match create_user_settings() {
+        Ok(s) => s,
+        Err(e) => return JjResult::error(format!("Failed to create settings: {}", e)),
+    };
+
+    let workspace = match load_named_workspace(handle, ws_name, &settings) {
+        Ok(ws) => ws,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let freshness =
+        match check_stale_working_copy(workspace.working_copy(), &wc_commit, &handle.repo) {
+            Ok(f) => f,
+            Err(e) => return JjResult::error(format!("Failed to check working copy freshness: {}", e)),
+        };
+
+    let is_stale = !matches!(freshness, WorkingCopyFreshness::Fresh);
+    JjResult::success(is_stale.to_string())
+}
+
+/// Recover a stale workspace, like `jj workspace update-stale`: create a new
+/// working-copy commit on top of the workspace's intended target commit (the
+/// one the latest view actually records it as checked out to), then recover
+/// the on-disk working copy onto that new commit. Recovering straight into
+/// the recorded commit itself would leave the workspace pointed at a frozen
+/// historical snapshot rather than a live `@` to build on - exactly the
+/// state staleness was supposed to fix.
+/// Returns JjResult with the new working-copy commit id (JSON string).
+#[no_mangle]
+pub extern "C" fn jj_workspace_update_stale(handle: *mut RepoHandle, workspace_name: *const c_char) -> JjResult {
+    use jj_lib::ref_name::WorkspaceNameBuf;
+
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let ws_name = unsafe {
+        if workspace_name.is_null() {
+            return JjResult::error("null workspace_name".to_string());
+        }
+        match CStr::from_ptr(workspace_name).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid workspace_name UTF-8: {}", e)),
+        }
+    };
+
+    let wc_commit_id = match handle
+        .repo
+        .view()
+        .wc_commit_ids()
+        .iter()
+        .find(|(ws_id, _)| ws_id.as_str() == ws_name)
+        .map(|(_, commit_id)| commit_id.clone())
+    {
+        Some(id) => id,
+        None => return JjResult::error(format!("Workspace not found: {}", ws_name)),
+    };
+
+    let intended_target_commit = match handle.repo.store().get_commit(&wc_commit_id) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(format!("Failed to get working copy commit: {}", e)),
+    };
+
+    // Create a new working-copy commit on top of the intended target, and
+    // record it as the workspace's wc commit.
+    let workspace_name_buf = WorkspaceNameBuf::from(ws_name.to_string());
+    let mut tx = handle.repo.start_transaction();
+    let new_commit = match tx
+        .repo_mut()
+        .new_commit(
+            vec![intended_target_commit.id().clone()],
+            intended_target_commit.tree(),
+        )
+        .write()
+    {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(format!("Failed to write commit: {}", e)),
+    };
+
+    if let Err(e) = tx
+        .repo_mut()
+        .set_wc_commit(workspace_name_buf, new_commit.id().clone())
+    {
+        return JjResult::error(format!("Failed to set working copy: {:?}", e));
+    }
+
+    let new_repo = match tx.commit("recover stale working copy") {
+        Ok(repo) => repo,
+        Err(e) => return JjResult::error(format!("Failed to commit transaction: {}", e)),
+    };
+    handle.repo = new_repo;
+
+    let settings = match create_user_settings() {
+        Ok(s) => s,
+        Err(e) => return JjResult::error(format!("Failed to create settings: {}", e)),
+    };
+
+    let mut workspace = match load_named_workspace(handle, ws_name, &settings) {
+        Ok(ws) => ws,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let mut locked_ws = match workspace.start_working_copy_mutation() {
+        Ok(l) => l,
+        Err(e) => return JjResult::error(format!("Failed to lock working copy: {}", e)),
+    };
+
+    if let Err(e) = locked_ws.locked_wc().recover(&new_commit) {
+        return JjResult::error(format!("Failed to recover working copy: {}", e));
+    }
+
+    if let Err(e) = locked_ws.finish(handle.repo.op_id().clone()) {
+        return JjResult::error(format!("Failed to finish working copy lock: {}", e));
+    }
+
+    JjResult::success(format!("\"{}\"", new_commit.id().hex()))
+}
+
+/// Snapshot the on-disk working copy (picking up edits that haven't been
+/// recorded yet) and write a new working-copy commit for the result, the
+/// same way the CLI does implicitly before every command.
+/// Returns the new working-copy commit id, or the existing one unchanged when
+/// the on-disk tree matches it (no commit is written in that case).
+fn snapshot_working_copy_impl(handle: &mut RepoHandle) -> Result<jj_lib::backend::CommitId, String> {
+    use jj_lib::gitignore::GitIgnoreFile;
+    use jj_lib::ref_name::WorkspaceNameBuf;
+    use jj_lib::working_copy::SnapshotOptions;
+
+    let settings = create_user_settings()?;
+    let mut workspace = load_named_workspace(handle, &handle.current_workspace.clone(), &settings)?;
+
+    let mut locked_ws = workspace
+        .start_working_copy_mutation()
+        .map_err(|e| format!("Failed to lock working copy: {}", e))?;
+
+    let options = SnapshotOptions {
+        base_ignores: GitIgnoreFile::empty(),
+        fsmonitor_settings: settings.fsmonitor_settings().unwrap_or_default(),
+        progress: None,
+        start_tracking_matcher: &EverythingMatcher,
+        max_new_file_size: settings.max_new_file_size().unwrap_or(u64::MAX),
+        conflict_marker_style: settings.conflict_marker_style().unwrap_or_default(),
+    };
+
+    let (new_tree_id, _stats) = locked_ws
+        .locked_wc()
+        .snapshot(&options)
+        .map_err(|e| format!("Failed to snapshot working copy: {}", e))?;
+
+    let wc_commit_id = handle
+        .repo
+        .view()
+        .wc_commit_ids()
+        .iter()
+        .find(|(ws_id, _)| ws_id.as_str() == handle.current_workspace)
+        .map(|(_, commit_id)| commit_id.clone())
+        .ok_or_else(|| "No working copy found for current workspace".to_string())?;
+
+    let old_wc_commit = handle
+        .repo
+        .store()
+        .get_commit(&wc_commit_id)
+        .map_err(|e| format!("Failed to get working copy commit: {}", e))?;
+
+    if new_tree_id == *old_wc_commit.tree_id() {
+        // Nothing changed on disk; the recorded commit is already current.
+        return Ok(wc_commit_id);
+    }
+
+    let workspace_name_buf = WorkspaceNameBuf::from(handle.current_workspace.clone());
+    let mut tx = handle.repo.start_transaction();
+
+    let new_commit = tx
+        .repo_mut()
+        .rewrite_commit(&old_wc_commit)
+        .set_tree_id(new_tree_id)
+        .write()
+        .map_err(|e| format!("Failed to write commit: {}", e))?;
+
+    tx.repo_mut()
+        .set_wc_commit(workspace_name_buf, new_commit.id().clone())
+        .map_err(|e| format!("Failed to set working copy: {:?}", e))?;
+
+    let new_repo = tx
+        .commit("snapshot working copy")
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    locked_ws
+        .finish(new_repo.op_id().clone())
+        .map_err(|e| format!("Failed to finish working copy lock: {}", e))?;
+
+    handle.repo = new_repo;
+    Ok(new_commit.id().clone())
+}
+
+/// Snapshot the on-disk working copy into a new working-copy commit.
+/// Returns JjResult with the new working-copy commit id (JSON string), or the
+/// existing one when nothing changed on disk.
+#[no_mangle]
+pub extern "C" fn jj_snapshot_working_copy(handle: *mut RepoHandle) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    match snapshot_working_copy_impl(handle) {
+        Ok(commit_id) => JjResult::success(format!("\"{}\"", commit_id.hex())),
+        Err(e) => JjResult::error(e),
+    }
+}
+
+/// Get file changes in the current working copy.
+/// If `snapshot_first` is true, the on-disk working copy is snapshotted
+/// (see `jj_snapshot_working_copy`) before computing the diff, so edits
+/// sitting on disk but not yet recorded are reflected.
+/// Added/deleted file pairs whose content similarity meets
+/// `DEFAULT_RENAME_SIMILARITY_THRESHOLD` are reported as a single entry with
+/// status "renamed" and `old_path` set, instead of a separate add and delete.
+/// Returns JjResult with JSON array of file change info on success
+#[no_mangle]
+pub extern "C" fn jj_get_working_copy_changes(handle: *mut RepoHandle, snapshot_first: bool) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    if snapshot_first {
+        if let Err(e) = snapshot_working_copy_impl(handle) {
+            return JjResult::error(e);
+        }
+    }
+
     // Find the current workspace's working copy commit
     let wc_commit_id = match handle
         .repo
@@ -704,7 +1190,9 @@ pub extern "C" fn jj_get_working_copy_changes(handle: *mut RepoHandle) -> JjResu
     let wc_tree: MergedTree = wc_commit.tree();
 
     // Collect file changes using diff_stream
-    let mut changes = Vec::new();
+    let mut modified = Vec::new();
+    let mut added: Vec<(String, String, bool)> = Vec::new(); // (path, content, has_conflict)
+    let mut deleted: Vec<(String, String, bool)> = Vec::new(); // (path, content, has_conflict)
     let matcher = EverythingMatcher;
 
     // Use diff_stream and collect synchronously
@@ -721,20 +1209,101 @@ pub extern "C" fn jj_get_working_copy_changes(handle: *mut RepoHandle) -> JjResu
                 Err(_) => continue,
             };
 
-            let status = if diff_values.before.is_absent() && !diff_values.after.is_absent() {
-                "added"
+            let path = entry.path.as_internal_file_string().to_string();
+            let has_conflict = diff_values.after.as_resolved().is_none();
+
+            if diff_values.before.is_absent() && !diff_values.after.is_absent() {
+                let (content, is_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.after);
+                if is_binary {
+                    modified.push(FileChangeInfo {
+                        path,
+                        status: "added".to_string(),
+                        has_conflict,
+                        old_path: None,
+                    });
+                } else {
+                    added.push((path, content, has_conflict));
+                }
             } else if !diff_values.before.is_absent() && diff_values.after.is_absent() {
-                "deleted"
+                let (content, is_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.before);
+                if is_binary {
+                    modified.push(FileChangeInfo {
+                        path,
+                        status: "deleted".to_string(),
+                        has_conflict,
+                        old_path: None,
+                    });
+                } else {
+                    deleted.push((path, content, has_conflict));
+                }
             } else {
-                "modified"
-            };
+                modified.push(FileChangeInfo {
+                    path,
+                    status: "modified".to_string(),
+                    has_conflict,
+                    old_path: None,
+                });
+            }
+        }
+    });
+
+    // Pair up added/deleted entries whose content is similar enough to be a
+    // rename, mirroring the rename detection done for `jj_get_diff`.
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for (di, (_, dcontent, _)) in deleted.iter().enumerate() {
+        for (ai, (_, acontent, _)) in added.iter().enumerate() {
+            let similarity = content_similarity(dcontent, acontent);
+            if similarity >= DEFAULT_RENAME_SIMILARITY_THRESHOLD {
+                candidates.push((similarity, di, ai));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut used_deleted: std::collections::HashSet<usize> = std::collections::HashSet::new();
+    let mut used_added: std::collections::HashSet<usize> = std::collections::HashSet::new();
+    let mut changes = Vec::new();
+
+    for (_similarity, di, ai) in candidates {
+        if used_deleted.contains(&di) || used_added.contains(&ai) {
+            continue;
+        }
+        used_deleted.insert(di);
+        used_added.insert(ai);
+
+        let (old_path, _, _) = &deleted[di];
+        let (new_path, _, has_conflict) = &added[ai];
+        changes.push(FileChangeInfo {
+            path: new_path.clone(),
+            status: "renamed".to_string(),
+            has_conflict: *has_conflict,
+            old_path: Some(old_path.clone()),
+        });
+    }
 
+    changes.extend(modified);
+    for (ai, (path, _, has_conflict)) in added.into_iter().enumerate() {
+        if !used_added.contains(&ai) {
             changes.push(FileChangeInfo {
-                path: entry.path.as_internal_file_string().to_string(),
-                status: status.to_string(),
+                path,
+                status: "added".to_string(),
+                has_conflict,
+                old_path: None,
             });
         }
-    });
+    }
+    for (di, (path, _, has_conflict)) in deleted.into_iter().enumerate() {
+        if !used_deleted.contains(&di) {
+            changes.push(FileChangeInfo {
+                path,
+                status: "deleted".to_string(),
+                has_conflict,
+                old_path: None,
+            });
+        }
+    }
 
     match serde_json::to_string(&changes) {
         Ok(json) => JjResult::success(json),
@@ -777,11 +1346,47 @@ pub extern "C" fn jj_list_operations(handle: *mut RepoHandle) -> JjResult {
         // Format timestamp
         let timestamp = format!("{}", metadata.time.start.timestamp.0);
 
+        let parent_ids: Vec<String> = op
+            .parent_ids()
+            .iter()
+            .map(|id| id.hex()[..12].to_string())
+            .collect();
+
+        // Change summary relative to the first parent, when there is
+        // exactly one (the common non-merge case).
+        let (commits_added, commits_removed) = match op.parent_ids() {
+            [parent_id] => {
+                let parent_op_result = pollster::block_on(op_store.read_operation(parent_id));
+                match parent_op_result {
+                    Ok(parent_op_data) => {
+                        let parent_op =
+                            Operation::new(op_store.clone(), parent_id.clone(), parent_op_data);
+                        match (op.view(), parent_op.view()) {
+                            (Ok(view), Ok(parent_view)) => {
+                                let heads = view.heads();
+                                let parent_heads = parent_view.heads();
+                                (
+                                    heads.difference(parent_heads).count(),
+                                    parent_heads.difference(heads).count(),
+                                )
+                            }
+                            _ => (0, 0),
+                        }
+                    }
+                    Err(_) => (0, 0),
+                }
+            }
+            _ => (0, 0),
+        };
+
         operations.push(OperationInfo {
             id: op_id[..12].to_string(), // Short ID
             description,
             timestamp,
             is_current,
+            parent_ids,
+            commits_added,
+            commits_removed,
         });
 
         // Add parent operations to visit
@@ -803,6 +1408,236 @@ pub extern "C" fn jj_list_operations(handle: *mut RepoHandle) -> JjResult {
     }
 }
 
+/// Walk the operation DAG from the current op head looking for an operation
+/// whose id starts with `op_id_prefix`.
+fn find_operation(handle: &RepoHandle, op_id_prefix: &str) -> Result<jj_lib::operation::Operation, String> {
+    find_operation_from(handle.repo.op_store(), handle.repo.operation(), op_id_prefix)
+}
+
+/// Walk the operation DAG backward from `head` looking for an operation
+/// whose id starts with `op_id_prefix`. Shared by the FFI entry points that
+/// already have a `RepoHandle` and the ones (like opening a repo at a past
+/// operation) that only have a loaded op store so far.
+fn find_operation_from(
+    op_store: &Arc<dyn jj_lib::op_store::OpStore>,
+    head: &jj_lib::operation::Operation,
+    op_id_prefix: &str,
+) -> Result<jj_lib::operation::Operation, String> {
+    use jj_lib::operation::Operation;
+    use std::collections::HashSet;
+
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut to_visit = vec![head.clone()];
+
+    while let Some(op) = to_visit.pop() {
+        let hex = op.id().hex();
+        if visited.contains(&hex) {
+            continue;
+        }
+        visited.insert(hex.clone());
+
+        if hex.starts_with(op_id_prefix) {
+            return Ok(op);
+        }
+
+        for parent_id in op.parent_ids() {
+            if !visited.contains(&parent_id.hex()) {
+                if let Ok(data) = pollster::block_on(op_store.read_operation(parent_id)) {
+                    to_visit.push(Operation::new(op_store.clone(), parent_id.clone(), data));
+                }
+            }
+        }
+    }
+
+    Err(format!("Operation not found: {}", op_id_prefix))
+}
+
+/// Reset the repo's view to that of `target_op`, recording a new operation
+/// for the change. Swaps `handle.repo` to the result and returns the new
+/// operation's short id.
+fn restore_to_operation(
+    handle: &mut RepoHandle,
+    target_op: &jj_lib::operation::Operation,
+    description: &str,
+) -> Result<String, String> {
+    let target_view = target_op
+        .view()
+        .map_err(|e| format!("Failed to load operation view: {}", e))?;
+
+    let mut tx = handle.repo.start_transaction();
+    tx.repo_mut().set_view(target_view.store_view().clone());
+
+    match tx.commit(description) {
+        Ok(new_repo) => {
+            let new_op_id = new_repo.operation().id().hex();
+            handle.repo = new_repo;
+            Ok(new_op_id[..12].to_string())
+        }
+        Err(e) => Err(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Restore the repo to a previous operation's state, like `jj op restore`.
+/// Returns JjResult with the new operation id (JSON string) on success.
+#[no_mangle]
+pub extern "C" fn jj_op_restore(handle: *mut RepoHandle, op_id: *const c_char) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let op_id_str = unsafe {
+        if op_id.is_null() {
+            return JjResult::error("null op_id".to_string());
+        }
+        match CStr::from_ptr(op_id).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid op_id UTF-8: {}", e)),
+        }
+    };
+
+    let target_op = match find_operation(handle, op_id_str) {
+        Ok(op) => op,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let description = format!("restore to operation {}", op_id_str);
+    match restore_to_operation(handle, &target_op, &description) {
+        Ok(new_op_id) => JjResult::success(format!("\"{}\"", new_op_id)),
+        Err(e) => JjResult::error(e),
+    }
+}
+
+/// Shared body of `jj_undo`/`jj_op_undo`: reverse the single most recent
+/// operation relative to its parent by restoring the repo view to that
+/// parent's state. Fails explicitly on a merge operation (multiple
+/// parents) rather than guessing which parent to undo to.
+fn undo_last_operation(handle: &mut RepoHandle) -> Result<String, String> {
+    let current_op = handle.repo.operation().clone();
+    let parent_id = match current_op.parent_ids() {
+        [] => return Err("Cannot undo: current operation has no parent".to_string()),
+        [id] => id.clone(),
+        _ => {
+            return Err(
+                "Cannot undo: current operation is a merge of multiple operations".to_string(),
+            )
+        }
+    };
+
+    let op_store = handle.repo.op_store();
+    let parent_op = match pollster::block_on(op_store.read_operation(&parent_id)) {
+        Ok(data) => jj_lib::operation::Operation::new(op_store.clone(), parent_id.clone(), data),
+        Err(e) => return Err(format!("Failed to load parent operation: {}", e)),
+    };
+
+    let description = format!("undo operation {}", current_op.id().hex());
+    restore_to_operation(handle, &parent_op, &description)
+}
+
+/// Undo the most recent operation, like `jj undo`.
+/// Returns JjResult with the new operation id (JSON string) on success.
+#[no_mangle]
+pub extern "C" fn jj_undo(handle: *mut RepoHandle) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    match undo_last_operation(handle) {
+        Ok(new_op_id) => JjResult::success(format!("\"{}\"", new_op_id)),
+        Err(e) => JjResult::error(e),
+    }
+}
+
+/// Undo the most recent operation, like `jj undo`. Identical to `jj_undo`;
+/// kept as the entry point named after the `jj op undo` subcommand for
+/// callers that group it alongside `jj_op_restore`/`jj_list_operations`.
+/// Returns JjResult with the new operation id (JSON string) on success.
+#[no_mangle]
+pub extern "C" fn jj_op_undo(handle: *mut RepoHandle) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    match undo_last_operation(handle) {
+        Ok(new_op_id) => JjResult::success(format!("\"{}\"", new_op_id)),
+        Err(e) => JjResult::error(e),
+    }
+}
+
+/// Build a map of workspace working-copy commit IDs (hex) to workspace names.
+fn workspace_commit_map(handle: &RepoHandle) -> std::collections::HashMap<String, String> {
+    let mut workspace_commits = std::collections::HashMap::new();
+    for (ws_id, commit_id) in handle.repo.view().wc_commit_ids() {
+        workspace_commits.insert(commit_id.hex(), ws_id.as_str().to_string());
+    }
+    workspace_commits
+}
+
+/// Serialize a single commit into a `RevisionInfo`.
+fn commit_to_revision_info(
+    handle: &RepoHandle,
+    commit: &Commit,
+    workspace_commits: &std::collections::HashMap<String, String>,
+    root_commit_id: &str,
+) -> RevisionInfo {
+    let commit_id_hex = commit.id().hex();
+    let is_root = commit_id_hex == root_commit_id;
+    // Use reverse_hex() for change IDs to get the base32-like encoding (e.g., "pmyysvqp")
+    let change_id = commit.change_id().reverse_hex();
+    let description = commit.description().to_string();
+
+    // Get author info
+    let signature = commit.author();
+    let author = signature.email.clone();
+
+    // Format timestamp as date + time
+    let commit_ts_secs = signature.timestamp.timestamp.0 / 1000;
+    let timestamp = chrono::DateTime::from_timestamp(commit_ts_secs, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Check if this is a working copy commit
+    let workspace_name = workspace_commits.get(&commit_id_hex).cloned();
+    let is_working_copy = workspace_name.is_some();
+
+    // Get parent commit IDs
+    let parents: Vec<String> = commit.parent_ids().iter().map(|id| id.hex()).collect();
+
+    // Get bookmarks for this commit
+    let mut bookmarks: Vec<String> = Vec::new();
+    for (name, target) in handle.repo.view().local_bookmarks() {
+        if target.added_ids().any(|id| id == commit.id()) {
+            bookmarks.push(name.as_str().to_string());
+        }
+    }
+
+    // Check if this commit is at git HEAD
+    let git_head_ref = handle.repo.view().git_head();
+    let git_head = git_head_ref.added_ids().any(|id| id == commit.id());
+
+    RevisionInfo {
+        id: commit_id_hex[..12].to_string(),
+        change_id: change_id[..12].to_string(),
+        description,
+        author,
+        timestamp,
+        bookmarks,
+        git_head,
+        is_working_copy,
+        workspace_name,
+        is_root,
+        parents: parents.iter().map(|p| p[..12].to_string()).collect(),
+    }
+}
+
 /// Get revision log for the repository
 /// Returns JjResult with JSON array of revision info on success
 #[no_mangle]
@@ -817,11 +1652,7 @@ pub extern "C" fn jj_get_log(handle: *mut RepoHandle) -> JjResult {
     };
 
     // Build a map of workspace commit IDs to workspace names
-    let mut workspace_commits: std::collections::HashMap<String, String> =
-        std::collections::HashMap::new();
-    for (ws_id, commit_id) in handle.repo.view().wc_commit_ids() {
-        workspace_commits.insert(commit_id.hex(), ws_id.as_str().to_string());
-    }
+    let workspace_commits = workspace_commit_map(handle);
 
     // Get the root commit ID
     let root_commit_id = handle.repo.store().root_commit_id().hex();
@@ -850,57 +1681,7 @@ pub extern "C" fn jj_get_log(handle: *mut RepoHandle) -> JjResult {
         visited.insert(commit_id_hex.clone());
 
         let is_root = commit_id_hex == root_commit_id;
-        // Use reverse_hex() for change IDs to get the base32-like encoding (e.g., "pmyysvqp")
-        let change_id = commit.change_id().reverse_hex();
-        let description = commit.description().to_string();
-
-        // Get author info
-        let signature = commit.author();
-        let author = signature.email.clone();
-
-        // Format timestamp as date + time
-        let commit_ts_secs = signature.timestamp.timestamp.0 / 1000;
-        let datetime = chrono::DateTime::from_timestamp(commit_ts_secs, 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-            .unwrap_or_else(|| "unknown".to_string());
-        let timestamp = datetime;
-
-        // Check if this is a working copy commit
-        let workspace_name = workspace_commits.get(&commit_id_hex).cloned();
-        let is_working_copy = workspace_name.is_some();
-
-        // Get parent commit IDs
-        let parents: Vec<String> = commit.parent_ids().iter().map(|id| id.hex()).collect();
-
-        // Get bookmarks for this commit
-        let mut bookmarks: Vec<String> = Vec::new();
-        let mut git_head = false;
-
-        for (name, target) in handle.repo.view().local_bookmarks() {
-            if target.added_ids().any(|id| id == commit.id()) {
-                bookmarks.push(name.as_str().to_string());
-            }
-        }
-
-        // Check if this commit is at git HEAD
-        let git_head_ref = handle.repo.view().git_head();
-        if git_head_ref.added_ids().any(|id| id == commit.id()) {
-            git_head = true;
-        }
-
-        revisions.push(RevisionInfo {
-            id: commit_id_hex[..12].to_string(),
-            change_id: change_id[..12].to_string(),
-            description,
-            author,
-            timestamp,
-            bookmarks,
-            git_head,
-            is_working_copy,
-            workspace_name,
-            is_root,
-            parents: parents.iter().map(|p| p[..12].to_string()).collect(),
-        });
+        revisions.push(commit_to_revision_info(handle, &commit, &workspace_commits, &root_commit_id));
 
         // Add parent commits to visit
         if !is_root {
@@ -920,10 +1701,313 @@ pub extern "C" fn jj_get_log(handle: *mut RepoHandle) -> JjResult {
     }
 }
 
+/// Evaluate a revset expression against `handle` and serialize the
+/// resolved commits to `RevisionInfo`, in the order the revset engine
+/// yields them (topological, descendants before ancestors).
+fn revisions_for_revset(handle: &RepoHandle, revset_str: &str) -> Result<Vec<RevisionInfo>, String> {
+    let commit_ids = evaluate_revset(handle, revset_str)?;
+
+    let workspace_commits = workspace_commit_map(handle);
+    let root_commit_id = handle.repo.store().root_commit_id().hex();
+
+    Ok(commit_ids
+        .iter()
+        .filter_map(|id| handle.repo.store().get_commit(id).ok())
+        .map(|commit| commit_to_revision_info(handle, &commit, &workspace_commits, &root_commit_id))
+        .collect())
+}
+
+/// Resolve an arbitrary revset expression (e.g. `@-`, `main`, `heads(::@)`,
+/// `description(glob:"wip*")`) against the repository.
+/// Returns JjResult with a JSON array of revision info on success.
+#[no_mangle]
+pub extern "C" fn jj_resolve_revset(handle: *mut RepoHandle, revset: *const c_char) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &*handle
+    };
+
+    let revset_str = unsafe {
+        if revset.is_null() {
+            return JjResult::error("null revset".to_string());
+        }
+        match CStr::from_ptr(revset).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid revset UTF-8: {}", e)),
+        }
+    };
+
+    let revisions = match revisions_for_revset(handle, revset_str) {
+        Ok(r) => r,
+        Err(e) => return JjResult::error(e),
+    };
+
+    match serde_json::to_string(&revisions) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
+/// Query the log for the revisions matched by an arbitrary revset (e.g.
+/// `@ | ancestors(mybookmark, 5)`, `heads()`, `author(foo)`), as a
+/// replacement for `jj_get_log`'s fixed-depth BFS. Bookmark names,
+/// change-id prefixes, and `@` resolve against the current workspace via
+/// the same symbol resolver as `jj_resolve_revset`.
+/// Returns JjResult with a JSON array of revision info, in topological
+/// order, on success.
+#[no_mangle]
+pub extern "C" fn jj_query_revset(handle: *mut RepoHandle, revset: *const c_char) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &*handle
+    };
+
+    let revset_str = unsafe {
+        if revset.is_null() {
+            return JjResult::error("null revset".to_string());
+        }
+        match CStr::from_ptr(revset).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid revset UTF-8: {}", e)),
+        }
+    };
+
+    let revisions = match revisions_for_revset(handle, revset_str) {
+        Ok(r) => r,
+        Err(e) => return JjResult::error(e),
+    };
+
+    match serde_json::to_string(&revisions) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
+/// Default similarity ratio (see `content_similarity`) at or above which an
+/// added/deleted pair is considered a rename. Used wherever rename detection
+/// isn't exposed as a caller-tunable parameter.
+const DEFAULT_RENAME_SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Ratio of matched (unchanged) lines to total lines across both sides of a
+/// Myers diff: 1.0 for identical content, 0.0 for completely disjoint
+/// content. Used as the similarity score for rename/copy detection.
+fn content_similarity(before: &str, after: &str) -> f64 {
+    let before_lines: Vec<&str> = before.lines().collect();
+    let after_lines: Vec<&str> = after.lines().collect();
+    let total = before_lines.len() + after_lines.len();
+    if total == 0 {
+        return 1.0;
+    }
+
+    let ops = myers_diff_ops(&before_lines, &after_lines);
+    let matched = ops.iter().filter(|op| matches!(op, LineOp::Equal(_))).count();
+    (2 * matched) as f64 / total as f64
+}
+
+/// Diff two trees into a unified-diff string, with optional similarity-based
+/// rename detection: added and deleted files whose content similarity meets
+/// `rename_threshold` (0.0-1.0) are paired up and rendered as `rename
+/// from`/`rename to` headers (with a unified diff body when the content
+/// isn't byte-identical) instead of an unrelated delete/add pair. Binary
+/// files never participate in rename detection.
+fn generate_tree_diff(
+    repo: &Arc<ReadonlyRepo>,
+    before_tree: &MergedTree,
+    after_tree: &MergedTree,
+    context_lines: usize,
+    detect_renames: bool,
+    rename_threshold: f64,
+) -> String {
+    use futures_util::StreamExt;
+    use std::collections::HashSet;
+
+    struct DiffEntry {
+        path: jj_lib::repo_path::RepoPathBuf,
+        before: jj_lib::merge::Merge<Option<jj_lib::backend::TreeValue>>,
+        after: jj_lib::merge::Merge<Option<jj_lib::backend::TreeValue>>,
+    }
+
+    let matcher = EverythingMatcher;
+    let diff_stream = before_tree.diff_stream(after_tree, &matcher);
+
+    let mut modified = Vec::new();
+    let mut added = Vec::new();
+    let mut deleted = Vec::new();
+
+    pollster::block_on(async {
+        futures_util::pin_mut!(diff_stream);
+        while let Some(entry) = diff_stream.next().await {
+            let Ok(values) = entry.values else { continue };
+            let before_is_file = !values.before.is_absent();
+            let after_is_file = !values.after.is_absent();
+            let diff_entry = DiffEntry {
+                path: entry.path,
+                before: values.before,
+                after: values.after,
+            };
+            if before_is_file && after_is_file {
+                modified.push(diff_entry);
+            } else if after_is_file {
+                added.push(diff_entry);
+            } else if before_is_file {
+                deleted.push(diff_entry);
+            }
+        }
+    });
+
+    let added_content: Vec<(String, bool)> = added
+        .iter()
+        .map(|e| {
+            let (content, binary, _) = materialize_file_content(repo, &e.path, &e.after);
+            (content, binary)
+        })
+        .collect();
+    let deleted_content: Vec<(String, bool)> = deleted
+        .iter()
+        .map(|e| {
+            let (content, binary, _) = materialize_file_content(repo, &e.path, &e.before);
+            (content, binary)
+        })
+        .collect();
+
+    // Greedy best-similarity-first pairing: each deleted/added path matches
+    // at most one counterpart.
+    let mut rename_pairs: Vec<(usize, usize, f64)> = Vec::new();
+    if detect_renames {
+        let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+        for (di, (dcontent, dbinary)) in deleted_content.iter().enumerate() {
+            if *dbinary {
+                continue;
+            }
+            for (ai, (acontent, abinary)) in added_content.iter().enumerate() {
+                if *abinary {
+                    continue;
+                }
+                let similarity = content_similarity(dcontent, acontent);
+                if similarity >= rename_threshold {
+                    candidates.push((similarity, di, ai));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        let mut used_deleted = HashSet::new();
+        let mut used_added = HashSet::new();
+        for (similarity, di, ai) in candidates {
+            if used_deleted.contains(&di) || used_added.contains(&ai) {
+                continue;
+            }
+            used_deleted.insert(di);
+            used_added.insert(ai);
+            rename_pairs.push((di, ai, similarity));
+        }
+    }
+    let matched_deleted: HashSet<usize> = rename_pairs.iter().map(|(di, _, _)| *di).collect();
+    let matched_added: HashSet<usize> = rename_pairs.iter().map(|(_, ai, _)| *ai).collect();
+
+    let mut diff_output = String::new();
+
+    for (di, ai, similarity) in &rename_pairs {
+        let old_path = deleted[*di].path.as_internal_file_string();
+        let new_path = added[*ai].path.as_internal_file_string();
+        diff_output.push_str(&format!("diff --git a/{} b/{}\n", old_path, new_path));
+        diff_output.push_str(&format!("rename from {}\n", old_path));
+        diff_output.push_str(&format!("rename to {}\n", new_path));
+        diff_output.push_str(&format!(
+            "similarity index {}%\n",
+            (similarity * 100.0).round() as u32
+        ));
+        if deleted_content[*di].0 != added_content[*ai].0 {
+            diff_output.push_str(&format!("--- a/{}\n", old_path));
+            diff_output.push_str(&format!("+++ b/{}\n", new_path));
+            diff_output.push_str(&generate_unified_diff(
+                &deleted_content[*di].0,
+                &added_content[*ai].0,
+                context_lines,
+            ));
+        }
+        diff_output.push('\n');
+    }
+
+    for entry in &modified {
+        let path = entry.path.as_internal_file_string();
+        diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+        diff_output.push_str(&format!("--- a/{}\n", path));
+        diff_output.push_str(&format!("+++ b/{}\n", path));
+
+        let (before_content, before_binary, _) = materialize_file_content(repo, &entry.path, &entry.before);
+        let (after_content, after_binary, _) = materialize_file_content(repo, &entry.path, &entry.after);
+
+        if before_binary || after_binary {
+            diff_output.push_str(&format!("Binary files a/{} and b/{} differ\n", path, path));
+        } else {
+            diff_output.push_str(&generate_unified_diff(&before_content, &after_content, context_lines));
+        }
+        diff_output.push('\n');
+    }
+
+    for (ai, entry) in added.iter().enumerate() {
+        if matched_added.contains(&ai) {
+            continue;
+        }
+        let path = entry.path.as_internal_file_string();
+        diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+        match git_file_mode(&entry.after) {
+            Some(mode) => diff_output.push_str(&format!("new file mode {}\n", mode)),
+            None => diff_output.push_str("new file\n"),
+        }
+        diff_output.push_str("--- /dev/null\n");
+        diff_output.push_str(&format!("+++ b/{}\n", path));
+
+        let (content, binary) = &added_content[ai];
+        if *binary {
+            diff_output.push_str(&format!("Binary files /dev/null and b/{} differ\n", path));
+        } else {
+            diff_output.push_str(&generate_unified_diff("", content, context_lines));
+        }
+        diff_output.push('\n');
+    }
+
+    for (di, entry) in deleted.iter().enumerate() {
+        if matched_deleted.contains(&di) {
+            continue;
+        }
+        let path = entry.path.as_internal_file_string();
+        diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+        match git_file_mode(&entry.before) {
+            Some(mode) => diff_output.push_str(&format!("deleted file mode {}\n", mode)),
+            None => diff_output.push_str("deleted file\n"),
+        }
+        diff_output.push_str(&format!("--- a/{}\n", path));
+        diff_output.push_str("+++ /dev/null\n");
+
+        let (content, binary) = &deleted_content[di];
+        if *binary {
+            diff_output.push_str(&format!("Binary files a/{} and /dev/null differ\n", path));
+        } else {
+            diff_output.push_str(&generate_unified_diff(content, "", context_lines));
+        }
+        diff_output.push('\n');
+    }
+
+    diff_output
+}
+
 /// Get diff for the working copy (changes from parent)
+/// `rename_threshold` is a similarity ratio in `[0.0, 1.0]`; ignored when
+/// `detect_renames` is false.
 /// Returns JjResult with unified diff string on success
 #[no_mangle]
-pub extern "C" fn jj_get_diff(handle: *mut RepoHandle) -> JjResult {
+pub extern "C" fn jj_get_diff(
+    handle: *mut RepoHandle,
+    context_lines: u32,
+    detect_renames: bool,
+    rename_threshold: f64,
+) -> JjResult {
     let handle = unsafe {
         if handle.is_null() {
             return JjResult::error("null repo handle".to_string());
@@ -964,134 +2048,325 @@ pub extern "C" fn jj_get_diff(handle: *mut RepoHandle) -> JjResult {
     let parent_tree: MergedTree = parent_commit.tree();
     let wc_tree: MergedTree = wc_commit.tree();
 
-    // Collect diff output
-    let mut diff_output = String::new();
-    let matcher = EverythingMatcher;
+    let diff_output = generate_tree_diff(
+        &handle.repo,
+        &parent_tree,
+        &wc_tree,
+        context_lines as usize,
+        detect_renames,
+        rename_threshold,
+    );
 
-    let diff_stream = parent_tree.diff_stream(&wc_tree, &matcher);
+    JjResult::success(diff_output)
+}
 
-    pollster::block_on(async {
-        use futures_util::StreamExt;
-        futures_util::pin_mut!(diff_stream);
+/// Same binary-content heuristic as `git diff`: a NUL byte anywhere in the
+/// content means "don't try to line-diff this", regardless of whether it
+/// also happens to be valid UTF-8 (NUL is a legal, if unusual, codepoint).
+fn is_binary_content(content: &[u8]) -> bool {
+    content.contains(&0)
+}
 
-        while let Some(entry) = diff_stream.next().await {
-            let diff_values = match entry.values {
-                Ok(v) => v,
-                Err(_) => continue,
+/// Git's file mode string for a tree value - "100644" for a regular file,
+/// "100755" for an executable one, "120000" for a symlink - so that a
+/// `new file`/`deleted file` diff header carries the mode `git apply`/`git
+/// am` need instead of losing it. `None` for anything without a git mode
+/// (absent, conflicted, a tree, or a submodule entry).
+fn git_file_mode(
+    tree_value: &jj_lib::merge::Merge<Option<jj_lib::backend::TreeValue>>,
+) -> Option<&'static str> {
+    use jj_lib::backend::TreeValue;
+    match tree_value.as_resolved() {
+        Some(Some(TreeValue::File { executable, .. })) => {
+            Some(if *executable { "100755" } else { "100644" })
+        }
+        Some(Some(TreeValue::Symlink(_))) => Some("120000"),
+        _ => None,
+    }
+}
+
+/// Resolve a tree value into displayable content, materializing conflicts
+/// into the standard conflict-marker representation and flagging binary
+/// blobs instead of mangling them through lossy UTF-8 conversion.
+///
+/// Returns `(content, is_binary, has_conflict)`.
+fn materialize_file_content(
+    repo: &Arc<ReadonlyRepo>,
+    path: &jj_lib::repo_path::RepoPath,
+    tree_value: &jj_lib::merge::Merge<Option<jj_lib::backend::TreeValue>>,
+) -> (String, bool, bool) {
+    use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+    use tokio::io::AsyncReadExt;
+
+    let materialized = match pollster::block_on(materialize_tree_value(
+        repo.store(),
+        path,
+        tree_value.clone(),
+    )) {
+        Ok(m) => m,
+        Err(_) => return (String::new(), false, false),
+    };
+
+    match materialized {
+        MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
+            (String::new(), false, false)
+        }
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut content = Vec::new();
+            if pollster::block_on(reader.read_to_end(&mut content)).is_err() {
+                return (String::new(), false, false);
+            }
+            if is_binary_content(&content) {
+                return (String::new(), true, false);
+            }
+            match String::from_utf8(content) {
+                Ok(s) => (s, false, false),
+                Err(_) => (String::new(), true, false),
+            }
+        }
+        MaterializedTreeValue::Symlink { target, .. } => (target, false, false),
+        MaterializedTreeValue::GitSubmodule(id) => {
+            (format!("Subproject commit {}", id.hex()), false, false)
+        }
+        MaterializedTreeValue::Tree(id) => (format!("Tree {}", id.hex()), false, false),
+        MaterializedTreeValue::FileConflict { contents, .. } => {
+            if is_binary_content(&contents) {
+                return (String::new(), true, true);
+            }
+            match String::from_utf8(contents) {
+                Ok(s) => (s, false, true),
+                Err(_) => (String::new(), true, true),
+            }
+        }
+        MaterializedTreeValue::OtherConflict { message } => (message, false, true),
+    }
+}
+
+/// One edit operation in a Myers edit script: a line kept as-is, removed
+/// from `before`, or inserted into `after`.
+enum LineOp {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+}
+
+/// Real Myers O(ND) shortest-edit-script diff between two line arrays.
+///
+/// Explores d-paths of increasing edit distance `d`, tracking for each
+/// diagonal `k = x - y` the furthest-reaching `x` reachable in `d` edits
+/// (`v`, a map from diagonal to x, seeded with `v[1] = 0` as the classic
+/// trick to make the `d = 0, k = 0` case fall out of the same formula).
+/// At each `d` we either extend from diagonal `k+1` (a "down" / insert
+/// move) or `k-1` (a "right" / delete move), then greedily extend
+/// diagonally while the lines match. A snapshot of `v` is kept per `d` so
+/// `backtrack` can walk from `(n, m)` back to `(0, 0)` and recover the
+/// sequence of inserts/deletes/equals that produced the shortest script.
+fn myers_diff_ops(before: &[&str], after: &[&str]) -> Vec<LineOp> {
+    use std::collections::HashMap;
+
+    let n = before.len() as i64;
+    let m = after.len() as i64;
+    let max_d = n + m;
+
+    if max_d == 0 {
+        return Vec::new();
+    }
+
+    let mut v: HashMap<i64, i64> = HashMap::new();
+    v.insert(1, 0);
+    let mut trace: Vec<HashMap<i64, i64>> = Vec::new();
+
+    let get = |v: &HashMap<i64, i64>, k: i64| v.get(&k).copied().unwrap_or(0);
+
+    let mut final_d = max_d;
+    'search: for d in 0..=max_d {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && get(&v, k - 1) < get(&v, k + 1)) {
+                get(&v, k + 1)
+            } else {
+                get(&v, k - 1) + 1
             };
+            let mut y = x - k;
 
-            let path = entry.path.as_internal_file_string();
+            while x < n && y < m && before[x as usize] == after[y as usize] {
+                x += 1;
+                y += 1;
+            }
 
-            // Determine the change type
-            let before_is_file = !diff_values.before.is_absent();
-            let after_is_file = !diff_values.after.is_absent();
+            v.insert(k, x);
 
-            if before_is_file && after_is_file {
-                // Modified file - get content diff
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str(&format!("--- a/{}\n", path));
-                diff_output.push_str(&format!("+++ b/{}\n", path));
-
-                // Get file contents for diff
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
-
-                // Generate line-based diff
-                let diff_lines = generate_unified_diff(&before_content, &after_content);
-                diff_output.push_str(&diff_lines);
-            } else if !before_is_file && after_is_file {
-                // Added file
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str("new file\n");
-                diff_output.push_str(&format!("--- /dev/null\n"));
-                diff_output.push_str(&format!("+++ b/{}\n", path));
-
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
-                for line in after_content.lines() {
-                    diff_output.push_str(&format!("+{}\n", line));
-                }
-            } else if before_is_file && !after_is_file {
-                // Deleted file
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str("deleted file\n");
-                diff_output.push_str(&format!("--- a/{}\n", path));
-                diff_output.push_str("+++ /dev/null\n");
+            if x >= n && y >= m {
+                final_d = d;
+                break 'search;
+            }
+        }
+    }
 
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                for line in before_content.lines() {
-                    diff_output.push_str(&format!("-{}\n", line));
-                }
+    // Backtrack from (n, m) to (0, 0) through the recorded snapshots,
+    // collecting ops in reverse order.
+    let mut ops: Vec<LineOp> = Vec::new();
+    let mut x = n;
+    let mut y = m;
+
+    for d in (0..=final_d).rev() {
+        let v = &trace[d as usize];
+        let k = x - y;
+
+        let prev_k = if k == -d || (k != d && get(v, k - 1) < get(v, k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = get(v, prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            ops.push(LineOp::Equal(before[(x - 1) as usize].to_string()));
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            if x == prev_x {
+                ops.push(LineOp::Insert(after[(y - 1) as usize].to_string()));
+            } else {
+                ops.push(LineOp::Delete(before[(x - 1) as usize].to_string()));
             }
-            diff_output.push('\n');
         }
-    });
 
-    JjResult::success(diff_output)
+        x = prev_x;
+        y = prev_y;
+    }
+
+    ops.reverse();
+    ops
 }
 
-fn get_file_content(
-    repo: &Arc<ReadonlyRepo>,
-    tree_value: &jj_lib::merge::Merge<Option<jj_lib::backend::TreeValue>>,
-) -> String {
-    use jj_lib::repo::Repo;
-    use tokio::io::AsyncReadExt;
+/// Group change indices into merged hunk ranges `[start, end)` over `ops`,
+/// keeping `context` unchanged lines on each side of a change and merging
+/// two hunks whose gap is within `2 * context` (so their context windows
+/// would otherwise overlap).
+fn hunk_ranges(ops: &[LineOp], context: usize) -> Vec<std::ops::Range<usize>> {
+    let change_indices: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, LineOp::Equal(_)))
+        .map(|(idx, _)| idx)
+        .collect();
 
-    // Try to get the first resolved value
-    if let Some(Some(value)) = tree_value.as_resolved() {
-        if let jj_lib::backend::TreeValue::File { id, .. } = value {
-            let read_result = pollster::block_on(
-                repo.store().read_file(&jj_lib::repo_path::RepoPath::root(), id),
-            );
-            if let Ok(mut reader) = read_result {
-                let mut content = Vec::new();
-                if pollster::block_on(reader.read_to_end(&mut content)).is_ok() {
-                    return String::from_utf8_lossy(&content).to_string();
-                }
-            }
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < change_indices.len() {
+        let start_change = change_indices[i];
+        let mut end_change = start_change;
+        let mut j = i;
+        while j + 1 < change_indices.len() && change_indices[j + 1] - end_change <= 2 * context + 1 {
+            j += 1;
+            end_change = change_indices[j];
         }
+
+        let start = start_change.saturating_sub(context);
+        let end = (end_change + context + 1).min(ops.len());
+        ranges.push(start..end);
+        i = j + 1;
     }
-    String::new()
+
+    ranges
 }
 
-fn generate_unified_diff(before: &str, after: &str) -> String {
+/// Render a Myers diff between `before` and `after` as a unified-diff body
+/// (no `diff --git`/`---`/`+++` header lines), with `context` lines of
+/// surrounding context per hunk. Handles files with no trailing newline by
+/// emitting `\ No newline at end of file`, same as POSIX `diff`.
+fn generate_unified_diff(before: &str, after: &str, context: usize) -> String {
     let before_lines: Vec<&str> = before.lines().collect();
     let after_lines: Vec<&str> = after.lines().collect();
 
-    let mut result = String::new();
+    if before_lines.is_empty() && after_lines.is_empty() {
+        return String::new();
+    }
 
-    // Simple line-by-line diff (could be improved with proper diff algorithm)
-    let max_lines = before_lines.len().max(after_lines.len());
+    let before_has_final_newline = before.is_empty() || before.ends_with('\n');
+    let after_has_final_newline = after.is_empty() || after.ends_with('\n');
 
-    if max_lines == 0 {
-        return result;
+    let mut ops = myers_diff_ops(&before_lines, &after_lines);
+
+    // A differing trailing newline is a real content difference (the final
+    // line's bytes differ even though `.lines()` strips the newline before
+    // `myers_diff_ops` ever sees it), but when that's the *only* difference
+    // the shared last line comes back as a single Equal op and no hunk gets
+    // emitted at all. Split it into a Delete+Insert pair of the same text so
+    // a one-line hunk is forced, same as `git diff` does for this case.
+    if before_has_final_newline != after_has_final_newline {
+        if let Some(LineOp::Equal(line)) = ops.last() {
+            let line = line.clone();
+            ops.pop();
+            ops.push(LineOp::Delete(line.clone()));
+            ops.push(LineOp::Insert(line));
+        }
     }
 
-    // Add a simple hunk header
-    result.push_str(&format!(
-        "@@ -1,{} +1,{} @@\n",
-        before_lines.len(),
-        after_lines.len()
-    ));
+    let last_before_idx = ops.iter().rposition(|op| !matches!(op, LineOp::Insert(_)));
+    let last_after_idx = ops.iter().rposition(|op| !matches!(op, LineOp::Delete(_)));
 
-    // Use a basic LCS-style diff
-    let mut i = 0;
-    let mut j = 0;
+    let mut result = String::new();
+    for range in hunk_ranges(&ops, context) {
+        let mut before_start = 1usize;
+        let mut after_start = 1usize;
+        for op in &ops[..range.start] {
+            match op {
+                LineOp::Equal(_) => {
+                    before_start += 1;
+                    after_start += 1;
+                }
+                LineOp::Delete(_) => before_start += 1,
+                LineOp::Insert(_) => after_start += 1,
+            }
+        }
 
-    while i < before_lines.len() || j < after_lines.len() {
-        if i < before_lines.len() && j < after_lines.len() && before_lines[i] == after_lines[j] {
-            result.push_str(&format!(" {}\n", before_lines[i]));
-            i += 1;
-            j += 1;
-        } else if j < after_lines.len()
-            && (i >= before_lines.len()
-                || !before_lines[i..].contains(&after_lines[j]))
-        {
-            result.push_str(&format!("+{}\n", after_lines[j]));
-            j += 1;
-        } else if i < before_lines.len() {
-            result.push_str(&format!("-{}\n", before_lines[i]));
-            i += 1;
+        let mut before_len = 0usize;
+        let mut after_len = 0usize;
+        let mut body = String::new();
+        for (offset, op) in ops[range.clone()].iter().enumerate() {
+            let abs_idx = range.start + offset;
+            let (prefix, content) = match op {
+                LineOp::Equal(s) => (' ', s.as_str()),
+                LineOp::Delete(s) => ('-', s.as_str()),
+                LineOp::Insert(s) => ('+', s.as_str()),
+            };
+            match op {
+                LineOp::Equal(_) => {
+                    before_len += 1;
+                    after_len += 1;
+                }
+                LineOp::Delete(_) => before_len += 1,
+                LineOp::Insert(_) => after_len += 1,
+            }
+
+            body.push(prefix);
+            body.push_str(content);
+            body.push('\n');
+
+            if !before_has_final_newline && !matches!(op, LineOp::Insert(_)) && Some(abs_idx) == last_before_idx {
+                body.push_str("\\ No newline at end of file\n");
+            } else if !after_has_final_newline && !matches!(op, LineOp::Delete(_)) && Some(abs_idx) == last_after_idx {
+                body.push_str("\\ No newline at end of file\n");
+            }
         }
+
+        // A zero-length side (e.g. a hunk that is purely an addition or
+        // purely a deletion) is reported at the line before the hunk, same
+        // as git/GNU diff - which may be 0 for a hunk at the very start of
+        // the file.
+        let before_display_start = if before_len == 0 { before_start.saturating_sub(1) } else { before_start };
+        let after_display_start = if after_len == 0 { after_start.saturating_sub(1) } else { after_start };
+
+        result.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            before_display_start, before_len, after_display_start, after_len
+        ));
+        result.push_str(&body);
     }
 
     result
@@ -1100,7 +2375,11 @@ fn generate_unified_diff(before: &str, after: &str) -> String {
 /// Get diff for a specific file in the working copy
 /// Returns JjResult with unified diff string on success
 #[no_mangle]
-pub extern "C" fn jj_get_file_diff(handle: *mut RepoHandle, path: *const c_char) -> JjResult {
+pub extern "C" fn jj_get_file_diff(
+    handle: *mut RepoHandle,
+    path: *const c_char,
+    context_lines: u32,
+) -> JjResult {
     let handle = unsafe {
         if handle.is_null() {
             return JjResult::error("null repo handle".to_string());
@@ -1184,30 +2463,61 @@ pub extern "C" fn jj_get_file_diff(handle: *mut RepoHandle, path: *const c_char)
                 diff_output.push_str(&format!("--- a/{}\n", entry_path));
                 diff_output.push_str(&format!("+++ b/{}\n", entry_path));
 
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
+                let (before_content, before_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.before);
+                let (after_content, after_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.after);
 
-                let diff_lines = generate_unified_diff(&before_content, &after_content);
-                diff_output.push_str(&diff_lines);
+                if before_binary || after_binary {
+                    diff_output.push_str(&format!(
+                        "Binary files a/{} and b/{} differ\n",
+                        entry_path, entry_path
+                    ));
+                } else {
+                    let diff_lines = generate_unified_diff(
+                        &before_content,
+                        &after_content,
+                        context_lines as usize,
+                    );
+                    diff_output.push_str(&diff_lines);
+                }
             } else if !before_is_file && after_is_file {
                 diff_output.push_str(&format!("diff --git a/{} b/{}\n", entry_path, entry_path));
-                diff_output.push_str("new file\n");
+                match git_file_mode(&diff_values.after) {
+                    Some(mode) => diff_output.push_str(&format!("new file mode {}\n", mode)),
+                    None => diff_output.push_str("new file\n"),
+                }
                 diff_output.push_str("--- /dev/null\n");
                 diff_output.push_str(&format!("+++ b/{}\n", entry_path));
 
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
-                for line in after_content.lines() {
-                    diff_output.push_str(&format!("+{}\n", line));
+                let (after_content, after_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.after);
+                if after_binary {
+                    diff_output.push_str(&format!(
+                        "Binary files /dev/null and b/{} differ\n",
+                        entry_path
+                    ));
+                } else {
+                    diff_output.push_str(&generate_unified_diff("", &after_content, context_lines as usize));
                 }
             } else if before_is_file && !after_is_file {
                 diff_output.push_str(&format!("diff --git a/{} b/{}\n", entry_path, entry_path));
-                diff_output.push_str("deleted file\n");
+                match git_file_mode(&diff_values.before) {
+                    Some(mode) => diff_output.push_str(&format!("deleted file mode {}\n", mode)),
+                    None => diff_output.push_str("deleted file\n"),
+                }
                 diff_output.push_str(&format!("--- a/{}\n", entry_path));
                 diff_output.push_str("+++ /dev/null\n");
 
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                for line in before_content.lines() {
-                    diff_output.push_str(&format!("-{}\n", line));
+                let (before_content, before_binary, _) =
+                    materialize_file_content(&handle.repo, &entry.path, &diff_values.before);
+                if before_binary {
+                    diff_output.push_str(&format!(
+                        "Binary files a/{} and /dev/null differ\n",
+                        entry_path
+                    ));
+                } else {
+                    diff_output.push_str(&generate_unified_diff(&before_content, "", context_lines as usize));
                 }
             }
         }
@@ -1216,12 +2526,65 @@ pub extern "C" fn jj_get_file_diff(handle: *mut RepoHandle, path: *const c_char)
     JjResult::success(diff_output)
 }
 
-/// Get the before/after content for a file in the working copy
-/// Returns JjResult with JSON containing before and after content
+/// Resolve the (before, after) commit pair to diff a single file against.
+/// `revision` of `None` means the working copy vs. its first parent;
+/// otherwise the given revset is resolved to a single commit and diffed
+/// against its first parent. `before` is `None` when `after` is a root
+/// commit, so callers should treat the path as added against an empty tree.
+fn resolve_file_diff_commits(handle: &RepoHandle, revision: Option<&str>) -> Result<(Option<Commit>, Commit), String> {
+    let after_commit = match revision {
+        None => {
+            let wc_commit_id = handle
+                .repo
+                .view()
+                .wc_commit_ids()
+                .iter()
+                .find(|(ws_id, _)| ws_id.as_str() == handle.current_workspace)
+                .map(|(_, commit_id)| commit_id.clone())
+                .ok_or_else(|| "No working copy found for current workspace".to_string())?;
+            handle
+                .repo
+                .store()
+                .get_commit(&wc_commit_id)
+                .map_err(|e| format!("Failed to get working copy commit: {}", e))?
+        }
+        Some(rev) => {
+            let commit_ids = evaluate_revset(handle, rev)?;
+            match commit_ids.len() {
+                0 => return Err(format!("Revision not found: {}", rev)),
+                1 => handle
+                    .repo
+                    .store()
+                    .get_commit(&commit_ids[0])
+                    .map_err(|e| format!("Failed to get commit: {}", e))?,
+                n => return Err(format!("Revision '{}' is ambiguous: resolved to {} commits", rev, n)),
+            }
+        }
+    };
+
+    let before_commit = match after_commit.parent_ids().first() {
+        Some(parent_id) => Some(
+            handle
+                .repo
+                .store()
+                .get_commit(parent_id)
+                .map_err(|e| format!("Failed to get parent commit: {}", e))?,
+        ),
+        None => None,
+    };
+
+    Ok((before_commit, after_commit))
+}
+
+/// Get the before/after content for a file.
+/// `revision` may be NULL to mean the working copy, or a revset string
+/// resolving to a single commit.
+/// Returns JjResult with JSON containing before and after content.
 #[no_mangle]
 pub extern "C" fn jj_get_file_contents(
     handle: *mut RepoHandle,
     path: *const c_char,
+    revision: *const c_char,
 ) -> JjResult {
     let handle = unsafe {
         if handle.is_null() {
@@ -1240,42 +2603,21 @@ pub extern "C" fn jj_get_file_contents(
         }
     };
 
-    // Find the current workspace's working copy commit
-    let wc_commit_id = match handle
-        .repo
-        .view()
-        .wc_commit_ids()
-        .iter()
-        .find(|(ws_id, _)| ws_id.as_str() == handle.current_workspace)
-    {
-        Some((_, commit_id)) => commit_id.clone(),
-        None => return JjResult::error("No working copy found for current workspace".to_string()),
-    };
-
-    // Get the working copy commit
-    let wc_commit: Commit = match handle.repo.store().get_commit(&wc_commit_id) {
-        Ok(commit) => commit,
-        Err(e) => return JjResult::error(format!("Failed to get working copy commit: {}", e)),
+    let revision_str = unsafe {
+        if revision.is_null() {
+            None
+        } else {
+            match CStr::from_ptr(revision).to_str() {
+                Ok(s) if s.is_empty() => None,
+                Ok(s) => Some(s),
+                Err(e) => return JjResult::error(format!("invalid revision UTF-8: {}", e)),
+            }
+        }
     };
 
-    // Get the parent commit(s)
-    let parent_ids = wc_commit.parent_ids();
-    if parent_ids.is_empty() {
-        // No parent, return empty before content
-        let contents = FileContents {
-            before: String::new(),
-            after: String::new(),
-            path: path_str.to_string(),
-        };
-        return match serde_json::to_string(&contents) {
-            Ok(json) => JjResult::success(json),
-            Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
-        };
-    }
-
-    let parent_commit: Commit = match handle.repo.store().get_commit(&parent_ids[0]) {
-        Ok(commit) => commit,
-        Err(e) => return JjResult::error(format!("Failed to get parent commit: {}", e)),
+    let (before_commit, after_commit) = match resolve_file_diff_commits(handle, revision_str) {
+        Ok(pair) => pair,
+        Err(e) => return JjResult::error(e),
     };
 
     // Build a repo path
@@ -1284,27 +2626,32 @@ pub extern "C" fn jj_get_file_contents(
         Err(e) => return JjResult::error(format!("Invalid path: {:?}", e)),
     };
 
-    // Get trees for comparison
-    let parent_tree: MergedTree = parent_commit.tree();
-    let wc_tree: MergedTree = wc_commit.tree();
-
-    // Get the file content at this path from both trees
-    let before_value = match parent_tree.path_value(&repo_path) {
-        Ok(v) => v,
-        Err(e) => return JjResult::error(format!("Failed to get before value: {}", e)),
-    };
-    let after_value = match wc_tree.path_value(&repo_path) {
+    let after_tree: MergedTree = after_commit.tree();
+    let after_value = match after_tree.path_value(&repo_path) {
         Ok(v) => v,
         Err(e) => return JjResult::error(format!("Failed to get after value: {}", e)),
     };
 
-    let before_content = get_file_content(&handle.repo, &before_value);
-    let after_content = get_file_content(&handle.repo, &after_value);
+    let (before_content, before_binary, before_conflict) = match &before_commit {
+        Some(commit) => {
+            let before_tree: MergedTree = commit.tree();
+            let before_value = match before_tree.path_value(&repo_path) {
+                Ok(v) => v,
+                Err(e) => return JjResult::error(format!("Failed to get before value: {}", e)),
+            };
+            materialize_file_content(&handle.repo, &repo_path, &before_value)
+        }
+        None => (String::new(), false, false),
+    };
+    let (after_content, after_binary, after_conflict) =
+        materialize_file_content(&handle.repo, &repo_path, &after_value);
 
     let contents = FileContents {
         before: before_content,
         after: after_content,
         path: path_str.to_string(),
+        is_binary: before_binary || after_binary,
+        has_conflict: before_conflict || after_conflict,
     };
 
     match serde_json::to_string(&contents) {
@@ -1313,10 +2660,166 @@ pub extern "C" fn jj_get_file_contents(
     }
 }
 
+/// Group a Myers line-op sequence into structured hunks, keeping `context`
+/// unchanged lines around each change. Built on the same `hunk_ranges`
+/// grouping used by `generate_unified_diff`, so for the same line ops and
+/// context the text and JSON diff APIs agree on hunk boundaries.
+fn ops_to_hunks(ops: &[LineOp], context: usize) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+
+    for range in hunk_ranges(ops, context) {
+        let mut before_start = 1usize;
+        let mut after_start = 1usize;
+        for op in &ops[..range.start] {
+            match op {
+                LineOp::Equal(_) => {
+                    before_start += 1;
+                    after_start += 1;
+                }
+                LineOp::Delete(_) => before_start += 1,
+                LineOp::Insert(_) => after_start += 1,
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut before_len = 0usize;
+        let mut after_len = 0usize;
+        for op in &ops[range] {
+            let (kind, content, in_before, in_after) = match op {
+                LineOp::Equal(s) => ("context", s, true, true),
+                LineOp::Delete(s) => ("removed", s, true, false),
+                LineOp::Insert(s) => ("added", s, false, true),
+            };
+            lines.push(DiffHunkLine {
+                kind: kind.to_string(),
+                content: content.clone(),
+            });
+            if in_before {
+                before_len += 1;
+            }
+            if in_after {
+                after_len += 1;
+            }
+        }
+
+        // Same zero-length-side convention as `generate_unified_diff`'s header.
+        if before_len == 0 {
+            before_start = before_start.saturating_sub(1);
+        }
+        if after_len == 0 {
+            after_start = after_start.saturating_sub(1);
+        }
+
+        hunks.push(DiffHunk {
+            before_start,
+            before_len,
+            after_start,
+            after_len,
+            lines,
+        });
+    }
+
+    hunks
+}
+
+/// Get structured unified-diff hunks for a single file, as a JSON
+/// alternative to the flat diff string from `jj_get_file_diff`.
+/// `revision` may be NULL to mean the working copy.
+/// Always uses 3 lines of context. A change confined to the file's final
+/// newline is not represented and yields no hunk.
+/// Returns JjResult with a JSON array of hunks on success.
+#[no_mangle]
+pub extern "C" fn jj_get_file_hunks(
+    handle: *mut RepoHandle,
+    path: *const c_char,
+    revision: *const c_char,
+) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &*handle
+    };
+
+    let path_str = unsafe {
+        if path.is_null() {
+            return JjResult::error("null path".to_string());
+        }
+        match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid UTF-8: {}", e)),
+        }
+    };
+
+    let revision_str = unsafe {
+        if revision.is_null() {
+            None
+        } else {
+            match CStr::from_ptr(revision).to_str() {
+                Ok(s) if s.is_empty() => None,
+                Ok(s) => Some(s),
+                Err(e) => return JjResult::error(format!("invalid revision UTF-8: {}", e)),
+            }
+        }
+    };
+
+    let (before_commit, after_commit) = match resolve_file_diff_commits(handle, revision_str) {
+        Ok(pair) => pair,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let repo_path = match jj_lib::repo_path::RepoPathBuf::from_internal_string(path_str) {
+        Ok(p) => p,
+        Err(e) => return JjResult::error(format!("Invalid path: {:?}", e)),
+    };
+
+    let after_tree: MergedTree = after_commit.tree();
+    let after_value = match after_tree.path_value(&repo_path) {
+        Ok(v) => v,
+        Err(e) => return JjResult::error(format!("Failed to get after value: {}", e)),
+    };
+
+    let (before_content, before_binary, _) = match &before_commit {
+        Some(commit) => {
+            let before_tree: MergedTree = commit.tree();
+            let before_value = match before_tree.path_value(&repo_path) {
+                Ok(v) => v,
+                Err(e) => return JjResult::error(format!("Failed to get before value: {}", e)),
+            };
+            materialize_file_content(&handle.repo, &repo_path, &before_value)
+        }
+        None => (String::new(), false, false),
+    };
+    let (after_content, after_binary, _) =
+        materialize_file_content(&handle.repo, &repo_path, &after_value);
+
+    if before_binary || after_binary {
+        return JjResult::error("Cannot produce line hunks for binary content".to_string());
+    }
+
+    let before_lines: Vec<&str> = before_content.lines().collect();
+    let after_lines: Vec<&str> = after_content.lines().collect();
+    let ops = myers_diff_ops(&before_lines, &after_lines);
+    let hunks = ops_to_hunks(&ops, 3);
+
+    match serde_json::to_string(&hunks) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
 /// Get diff for a revision compared to its parent
+/// `rename_threshold` is a similarity ratio in `[0.0, 1.0]`; ignored when
+/// `detect_renames` is false.
 /// Returns JjResult with unified diff string on success
 #[no_mangle]
-pub extern "C" fn jj_get_revision_diff(handle: *mut RepoHandle, revision_id: *const c_char) -> JjResult {
+pub extern "C" fn jj_get_revision_diff(
+    handle: *mut RepoHandle,
+    revision_id: *const c_char,
+    context_lines: u32,
+    detect_renames: bool,
+    rename_threshold: f64,
+) -> JjResult {
     let handle = unsafe {
         if handle.is_null() {
             return JjResult::error("null repo handle".to_string());
@@ -1394,74 +2897,27 @@ pub extern "C" fn jj_get_revision_diff(handle: *mut RepoHandle, revision_id: *co
     let parent_tree: MergedTree = parent_commit.tree();
     let commit_tree: MergedTree = commit.tree();
 
-    // Collect diff output
-    let mut diff_output = String::new();
-    let matcher = EverythingMatcher;
-
-    let diff_stream = parent_tree.diff_stream(&commit_tree, &matcher);
-
-    pollster::block_on(async {
-        use futures_util::StreamExt;
-        futures_util::pin_mut!(diff_stream);
+    let diff_output = generate_tree_diff(
+        &handle.repo,
+        &parent_tree,
+        &commit_tree,
+        context_lines as usize,
+        detect_renames,
+        rename_threshold,
+    );
 
-        while let Some(entry) = diff_stream.next().await {
-            let diff_values = match entry.values {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
+    JjResult::success(diff_output)
+}
 
-            let path = entry.path.as_internal_file_string();
-
-            let before_is_file = !diff_values.before.is_absent();
-            let after_is_file = !diff_values.after.is_absent();
-
-            if before_is_file && after_is_file {
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str(&format!("--- a/{}\n", path));
-                diff_output.push_str(&format!("+++ b/{}\n", path));
-
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
-
-                let diff_lines = generate_unified_diff(&before_content, &after_content);
-                diff_output.push_str(&diff_lines);
-            } else if !before_is_file && after_is_file {
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str("new file\n");
-                diff_output.push_str("--- /dev/null\n");
-                diff_output.push_str(&format!("+++ b/{}\n", path));
-
-                let after_content = get_file_content(&handle.repo, &diff_values.after);
-                for line in after_content.lines() {
-                    diff_output.push_str(&format!("+{}\n", line));
-                }
-            } else if before_is_file && !after_is_file {
-                diff_output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
-                diff_output.push_str("deleted file\n");
-                diff_output.push_str(&format!("--- a/{}\n", path));
-                diff_output.push_str("+++ /dev/null\n");
-
-                let before_content = get_file_content(&handle.repo, &diff_values.before);
-                for line in before_content.lines() {
-                    diff_output.push_str(&format!("-{}\n", line));
-                }
-            }
-            diff_output.push('\n');
-        }
-    });
-
-    JjResult::success(diff_output)
-}
-
-/// Close a repository handle and free its memory
-#[no_mangle]
-pub extern "C" fn jj_close_repo(handle: *mut RepoHandle) {
-    if !handle.is_null() {
-        unsafe {
-            drop(Box::from_raw(handle));
-        }
-    }
-}
+/// Close a repository handle and free its memory
+#[no_mangle]
+pub extern "C" fn jj_close_repo(handle: *mut RepoHandle) {
+    if !handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(handle));
+        }
+    }
+}
 
 /// Free a JjResult's memory
 #[no_mangle]
@@ -1488,6 +2944,92 @@ pub extern "C" fn jj_free_string(s: *mut c_char) {
     }
 }
 
+/// Whether `candidate` is `head` itself or an ancestor of it, answered via a
+/// best-first walk over the commit index's generation numbers rather than an
+/// unbounded (or depth-capped) walk over `parent_ids()`.
+///
+/// The walk expands the highest-generation frontier entry first using a
+/// binary max-heap, and can stop as soon as every remaining frontier entry
+/// has a generation number below `candidate`'s: nothing lower-generation can
+/// be an ancestor of something higher-generation, so `candidate` is
+/// unreachable from here on. This terminates correctly no matter how deep the
+/// history is, with no magic depth constant.
+fn is_ancestor_via_index(
+    repo: &Arc<ReadonlyRepo>,
+    candidate: &jj_lib::backend::CommitId,
+    head: &jj_lib::backend::CommitId,
+) -> bool {
+    use jj_lib::index::IndexPosition;
+    use std::cmp::Ordering;
+    use std::collections::{BinaryHeap, HashSet};
+
+    let index = repo.readonly_index().as_composite();
+
+    let (candidate_pos, candidate_gen) = match index.entry_by_id(candidate) {
+        Some(entry) => (entry.position(), entry.generation_number()),
+        None => return false,
+    };
+    let head_entry = match index.entry_by_id(head) {
+        Some(entry) => entry,
+        None => return false,
+    };
+
+    struct Frontier {
+        generation: u32,
+        position: IndexPosition,
+    }
+    impl PartialEq for Frontier {
+        fn eq(&self, other: &Self) -> bool {
+            self.generation == other.generation
+        }
+    }
+    impl Eq for Frontier {}
+    impl Ord for Frontier {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.generation.cmp(&other.generation)
+        }
+    }
+    impl PartialOrd for Frontier {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    let mut heap = BinaryHeap::new();
+    heap.push(Frontier {
+        generation: head_entry.generation_number(),
+        position: head_entry.position(),
+    });
+    let mut visited: HashSet<IndexPosition> = HashSet::new();
+
+    while let Some(Frontier { generation, position }) = heap.pop() {
+        if position == candidate_pos {
+            return true;
+        }
+        if generation < candidate_gen {
+            // Everything still in the heap (and everything they could ever
+            // push) has generation <= this one, so candidate is unreachable.
+            return false;
+        }
+        if !visited.insert(position) {
+            continue;
+        }
+        let entry = index.entry_by_pos(position);
+        for parent_pos in entry.parent_positions() {
+            if visited.contains(&parent_pos) {
+                continue;
+            }
+            let parent_entry = index.entry_by_pos(parent_pos);
+            heap.push(Frontier {
+                generation: parent_entry.generation_number(),
+                position: parent_pos,
+            });
+        }
+    }
+
+    false
+}
+
 /// Set a bookmark to point to a specific revision.
 ///
 /// Parameters:
@@ -1605,43 +3147,14 @@ pub extern "C" fn jj_set_bookmark(
             }
         }
 
-        // Check if target_commit is an ancestor of any remote head
-        // by walking down from remote heads to see if we reach target
-        if !remote_heads.is_empty() {
-            let mut is_immutable = false;
-            let mut to_check: Vec<jj_lib::backend::CommitId> = remote_heads;
-            let mut checked: HashSet<String> = HashSet::new();
-            let max_depth = 200;
-
-            for _ in 0..max_depth {
-                if to_check.is_empty() {
-                    break;
-                }
-
-                let commit_id = to_check.pop().unwrap();
-                let hex = commit_id.hex();
-                if checked.contains(&hex) {
-                    continue;
-                }
-                checked.insert(hex);
-
-                if &commit_id == target_commit.id() {
-                    // Target is an ancestor of remote bookmark - it's immutable
-                    is_immutable = true;
-                    break;
-                }
-
-                // Add parents to check
-                if let Ok(c) = handle.repo.store().get_commit(&commit_id) {
-                    for parent_id in c.parent_ids() {
-                        to_check.push(parent_id.clone());
-                    }
-                }
-            }
+        // Check if target_commit is an ancestor of any remote head, via the
+        // commit index rather than a hand-rolled walk.
+        let is_immutable = remote_heads
+            .iter()
+            .any(|head| is_ancestor_via_index(&handle.repo, target_commit.id(), head));
 
-            if is_immutable {
-                return JjResult::error("Cannot set bookmark on immutable revision (already pushed)".to_string());
-            }
+        if is_immutable {
+            return JjResult::error("Cannot set bookmark on immutable revision (already pushed)".to_string());
         }
     }
 
@@ -1650,42 +3163,10 @@ pub extern "C" fn jj_set_bookmark(
         // Get current bookmark target
         let current_target = handle.repo.view().get_local_bookmark(&ref_name);
         if let Some(ref_target) = current_target.as_normal() {
-            // Check if new target is an ancestor of current target
-            if let Ok(current_commit) = handle.repo.store().get_commit(ref_target) {
-                // Simple ancestor check: walk from current to see if we reach target
-                let mut is_backwards = false;
-                let mut ancestors_to_check: Vec<jj_lib::backend::CommitId> = vec![current_commit.id().clone()];
-                let mut checked: HashSet<String> = HashSet::new();
-                let max_depth = 100; // Limit search depth
-
-                for _ in 0..max_depth {
-                    if ancestors_to_check.is_empty() {
-                        break;
-                    }
-
-                    let commit_id = ancestors_to_check.pop().unwrap();
-                    let hex = commit_id.hex();
-                    if checked.contains(&hex) {
-                        continue;
-                    }
-                    checked.insert(hex);
-
-                    if &commit_id == target_commit.id() {
-                        // Target is an ancestor of current - this is backwards
-                        is_backwards = true;
-                        break;
-                    }
-
-                    if let Ok(c) = handle.repo.store().get_commit(&commit_id) {
-                        for parent_id in c.parent_ids() {
-                            ancestors_to_check.push(parent_id.clone());
-                        }
-                    }
-                }
-
-                if is_backwards {
-                    return JjResult::error("Cannot move bookmark backwards (use allow_backwards flag)".to_string());
-                }
+            // Moving backwards means the new target is an ancestor of the
+            // bookmark's current target - check via the commit index.
+            if is_ancestor_via_index(&handle.repo, target_commit.id(), ref_target) {
+                return JjResult::error("Cannot move bookmark backwards (use allow_backwards flag)".to_string());
             }
         }
     }
@@ -1710,3 +3191,839 @@ pub extern "C" fn jj_set_bookmark(
         Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
     }
 }
+
+/// The state of a local bookmark's target, as reported by `jj_get_bookmark`.
+#[derive(Serialize)]
+struct BookmarkInfo {
+    /// "absent", "normal", or "conflict".
+    state: String,
+    /// Set when `state` is "normal": the commit the bookmark points to.
+    commit_id: Option<String>,
+    /// Set when `state` is "conflict": the conflict's added (winning
+    /// candidate) commit ids.
+    added_ids: Vec<String>,
+    /// Set when `state` is "conflict": the conflict's removed (base) commit
+    /// ids.
+    removed_ids: Vec<String>,
+}
+
+/// Get the current target of a local bookmark, including conflict state.
+///
+/// A bookmark can legitimately point at more than one commit after a
+/// concurrent operation or a divergent `jj git fetch`; unlike
+/// `jj_set_bookmark`, which always writes a resolved `RefTarget::normal`,
+/// this reports that conflict (as `added_ids`/`removed_ids`) rather than
+/// collapsing it.
+/// Returns JjResult with a `BookmarkInfo` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_get_bookmark(handle: *mut RepoHandle, name: *const c_char) -> JjResult {
+    use jj_lib::ref_name::RefNameBuf;
+
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &*handle
+    };
+
+    let bookmark_name = unsafe {
+        if name.is_null() {
+            return JjResult::error("null bookmark name".to_string());
+        }
+        match CStr::from_ptr(name).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid bookmark name UTF-8: {}", e)),
+        }
+    };
+
+    let ref_name = RefNameBuf::from(bookmark_name.to_string());
+    let target = handle.repo.view().get_local_bookmark(&ref_name);
+
+    let info = if target.is_absent() {
+        BookmarkInfo {
+            state: "absent".to_string(),
+            commit_id: None,
+            added_ids: Vec::new(),
+            removed_ids: Vec::new(),
+        }
+    } else if let Some(commit_id) = target.as_normal() {
+        BookmarkInfo {
+            state: "normal".to_string(),
+            commit_id: Some(commit_id.hex()),
+            added_ids: Vec::new(),
+            removed_ids: Vec::new(),
+        }
+    } else {
+        BookmarkInfo {
+            state: "conflict".to_string(),
+            commit_id: None,
+            added_ids: target.added_ids().map(|id| id.hex()).collect(),
+            removed_ids: target.removed_ids().map(|id| id.hex()).collect(),
+        }
+    };
+
+    match serde_json::to_string(&info) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
+/// Resolve a conflicted local bookmark by pointing it at a single winning
+/// revision, like picking one side of a divergent bookmark in `jj`.
+/// `revision_id` is a commit id prefix, resolved the same way as in
+/// `jj_set_bookmark`; it does not need to be one of the conflict's existing
+/// added ids.
+/// Returns JjResult with a `MutationResult` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_resolve_bookmark_conflict(
+    handle: *mut RepoHandle,
+    name: *const c_char,
+    revision_id: *const c_char,
+) -> JjResult {
+    use jj_lib::op_store::RefTarget;
+    use jj_lib::ref_name::RefNameBuf;
+    use std::collections::HashSet;
+
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let bookmark_name = unsafe {
+        if name.is_null() {
+            return JjResult::error("null bookmark name".to_string());
+        }
+        match CStr::from_ptr(name).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid bookmark name UTF-8: {}", e)),
+        }
+    };
+
+    let revision_str = unsafe {
+        if revision_id.is_null() {
+            return JjResult::error("null revision_id".to_string());
+        }
+        match CStr::from_ptr(revision_id).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid revision_id UTF-8: {}", e)),
+        }
+    };
+
+    let ref_name = RefNameBuf::from(bookmark_name.to_string());
+    let current_target = handle.repo.view().get_local_bookmark(&ref_name);
+    if !current_target.has_conflict() {
+        return JjResult::error(format!("Bookmark {} is not conflicted", bookmark_name));
+    }
+
+    // Find the winning commit by ID prefix - walk from working copy commits.
+    let winning_commit = {
+        let mut found: Option<Commit> = None;
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut to_visit: Vec<jj_lib::backend::CommitId> = Vec::new();
+
+        for (_ws_id, commit_id) in handle.repo.view().wc_commit_ids() {
+            to_visit.push(commit_id.clone());
+        }
+
+        while let Some(commit_id) = to_visit.pop() {
+            let hex = commit_id.hex();
+            if visited.contains(&hex) {
+                continue;
+            }
+            visited.insert(hex.clone());
+
+            if hex.starts_with(revision_str) {
+                match handle.repo.store().get_commit(&commit_id) {
+                    Ok(c) => {
+                        found = Some(c);
+                        break;
+                    }
+                    Err(_) => continue,
+                }
+            }
+
+            if let Ok(c) = handle.repo.store().get_commit(&commit_id) {
+                for parent_id in c.parent_ids() {
+                    if !visited.contains(&parent_id.hex()) {
+                        to_visit.push(parent_id.clone());
+                    }
+                }
+            }
+        }
+
+        match found {
+            Some(c) => c,
+            None => return JjResult::error(format!("Revision not found: {}", revision_str)),
+        }
+    };
+
+    let mut tx = handle.repo.start_transaction();
+
+    tx.repo_mut().set_local_bookmark_target(
+        &ref_name,
+        RefTarget::normal(winning_commit.id().clone()),
+    );
+
+    let description = format!(
+        "resolve conflicted bookmark {} to {}",
+        bookmark_name, revision_str
+    );
+    match tx.commit(&description) {
+        Ok(new_repo) => {
+            let op_id = new_repo.operation().id().clone();
+            handle.repo = new_repo;
+            mutation_result_json(handle, op_id)
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Result of a mutating operation: the frontend refreshes its log/working-
+/// copy state from these two ids rather than re-deriving them.
+#[derive(Serialize)]
+struct MutationResult {
+    operation_id: String,
+    working_copy_commit_id: String,
+}
+
+/// Resolve a revset spec that must name exactly one commit, e.g. a revision
+/// argument to `jj_describe`/`jj_abandon`/`jj_squash`.
+fn resolve_single_commit(handle: &RepoHandle, spec: &str) -> Result<Commit, String> {
+    let ids = resolve_revision_specs(handle, std::slice::from_ref(&spec.to_string()))?;
+    handle
+        .repo
+        .store()
+        .get_commit(&ids[0])
+        .map_err(|e| format!("Failed to get commit: {}", e))
+}
+
+/// The current workspace's working-copy commit id, after a transaction has
+/// been committed (so the frontend knows what to check out/refresh).
+fn current_wc_commit_id(handle: &RepoHandle) -> String {
+    handle
+        .repo
+        .view()
+        .wc_commit_ids()
+        .iter()
+        .find(|(ws_id, _)| ws_id.as_str() == handle.current_workspace)
+        .map(|(_, commit_id)| commit_id.hex())
+        .unwrap_or_default()
+}
+
+fn mutation_result_json(handle: &RepoHandle, operation_id: jj_lib::op_store::OperationId) -> JjResult {
+    let result = MutationResult {
+        operation_id: operation_id.hex(),
+        working_copy_commit_id: current_wc_commit_id(handle),
+    };
+    match serde_json::to_string(&result) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
+/// Set a commit's description, like `jj describe`.
+/// `rev` is a revset spec resolving to exactly one commit; `message` is the
+/// new description text.
+/// Returns JjResult with a `MutationResult` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_describe(
+    handle: *mut RepoHandle,
+    rev: *const c_char,
+    message: *const c_char,
+) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let rev_str = unsafe {
+        if rev.is_null() {
+            return JjResult::error("null rev".to_string());
+        }
+        match CStr::from_ptr(rev).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid rev UTF-8: {}", e)),
+        }
+    };
+
+    let message_str = unsafe {
+        if message.is_null() {
+            return JjResult::error("null message".to_string());
+        }
+        match CStr::from_ptr(message).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid message UTF-8: {}", e)),
+        }
+    };
+
+    let commit = match resolve_single_commit(handle, rev_str) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let mut tx = handle.repo.start_transaction();
+
+    if let Err(e) = tx
+        .repo_mut()
+        .rewrite_commit(&commit)
+        .set_description(message_str)
+        .write()
+    {
+        return JjResult::error(format!("Failed to rewrite commit: {}", e));
+    }
+
+    if let Err(e) = tx.repo_mut().rebase_descendants() {
+        return JjResult::error(format!("Failed to rebase descendants: {}", e));
+    }
+
+    let description = format!("describe commit {}", &commit.id().hex()[..12]);
+    match tx.commit(&description) {
+        Ok(new_repo) => {
+            let op_id = new_repo.operation().id().clone();
+            handle.repo = new_repo;
+            mutation_result_json(handle, op_id)
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Create a new working-copy commit on top of `parent_revs`, like `jj new`.
+/// `parent_revs` is a comma-separated list of revset specs, each resolving
+/// to exactly one commit; an empty string means "on top of `@`", matching
+/// `jj new` with no arguments.
+/// Returns JjResult with a `MutationResult` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_new(handle: *mut RepoHandle, parent_revs: *const c_char) -> JjResult {
+    use jj_lib::ref_name::WorkspaceNameBuf;
+
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let parent_revs_str = unsafe {
+        if parent_revs.is_null() {
+            return JjResult::error("null parent_revs".to_string());
+        }
+        match CStr::from_ptr(parent_revs).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid parent_revs UTF-8: {}", e)),
+        }
+    };
+
+    let specs: Vec<String> = if parent_revs_str.trim().is_empty() {
+        vec!["@".to_string()]
+    } else {
+        parent_revs_str.split(',').map(|s| s.trim().to_string()).collect()
+    };
+
+    let parent_ids = match resolve_revision_specs(handle, &specs) {
+        Ok(ids) => ids,
+        Err(e) => return JjResult::error(e),
+    };
+
+    let parent_commits: Vec<Commit> = match parent_ids
+        .iter()
+        .map(|id| handle.repo.store().get_commit(id))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(commits) => commits,
+        Err(e) => return JjResult::error(format!("Failed to load parent commit: {}", e)),
+    };
+
+    let merged_parent_tree =
+        match jj_lib::merged_tree::merge_commit_trees(handle.repo.as_ref(), &parent_commits) {
+            Ok(tree) => tree,
+            Err(e) => return JjResult::error(format!("Failed to merge parent trees: {}", e)),
+        };
+
+    let mut tx = handle.repo.start_transaction();
+
+    let new_commit = match tx
+        .repo_mut()
+        .new_commit(
+            parent_commits.iter().map(|c| c.id().clone()).collect(),
+            merged_parent_tree,
+        )
+        .write()
+    {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(format!("Failed to write commit: {}", e)),
+    };
+
+    let workspace_name_buf = WorkspaceNameBuf::from(handle.current_workspace.clone());
+    if let Err(e) = tx
+        .repo_mut()
+        .set_wc_commit(workspace_name_buf, new_commit.id().clone())
+    {
+        return JjResult::error(format!("Failed to set working copy: {:?}", e));
+    }
+
+    let description = "new empty commit".to_string();
+    match tx.commit(&description) {
+        Ok(new_repo) => {
+            let op_id = new_repo.operation().id().clone();
+            handle.repo = new_repo;
+            mutation_result_json(handle, op_id)
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Abandon a commit and rebase its descendants onto its parents, like
+/// `jj abandon`. `rev` is a revset spec resolving to exactly one commit.
+/// Returns JjResult with a `MutationResult` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_abandon(handle: *mut RepoHandle, rev: *const c_char) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let rev_str = unsafe {
+        if rev.is_null() {
+            return JjResult::error("null rev".to_string());
+        }
+        match CStr::from_ptr(rev).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid rev UTF-8: {}", e)),
+        }
+    };
+
+    let commit = match resolve_single_commit(handle, rev_str) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(e),
+    };
+
+    if commit.id() == handle.repo.store().root_commit_id() {
+        return JjResult::error("Cannot abandon the root commit".to_string());
+    }
+
+    let mut tx = handle.repo.start_transaction();
+
+    tx.repo_mut().record_abandoned_commit(commit.id().clone());
+
+    if let Err(e) = tx.repo_mut().rebase_descendants() {
+        return JjResult::error(format!("Failed to rebase descendants: {}", e));
+    }
+
+    let description = format!("abandon commit {}", &commit.id().hex()[..12]);
+    match tx.commit(&description) {
+        Ok(new_repo) => {
+            let op_id = new_repo.operation().id().clone();
+            handle.repo = new_repo;
+            mutation_result_json(handle, op_id)
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Move `src`'s changes into `dest`, like `jj squash --from src --into dest`.
+/// Both `src` and `dest` are revset specs resolving to exactly one commit
+/// each. `src`'s description is carried over only if `dest`'s description is
+/// empty; `src` is then abandoned and descendants are rebased.
+/// Returns JjResult with a `MutationResult` JSON object on success.
+#[no_mangle]
+pub extern "C" fn jj_squash(
+    handle: *mut RepoHandle,
+    src: *const c_char,
+    dest: *const c_char,
+) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let src_str = unsafe {
+        if src.is_null() {
+            return JjResult::error("null src".to_string());
+        }
+        match CStr::from_ptr(src).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid src UTF-8: {}", e)),
+        }
+    };
+
+    let dest_str = unsafe {
+        if dest.is_null() {
+            return JjResult::error("null dest".to_string());
+        }
+        match CStr::from_ptr(dest).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid dest UTF-8: {}", e)),
+        }
+    };
+
+    let src_commit = match resolve_single_commit(handle, src_str) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(e),
+    };
+    let dest_commit = match resolve_single_commit(handle, dest_str) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(e),
+    };
+
+    if src_commit.id() == dest_commit.id() {
+        return JjResult::error("Cannot squash a commit into itself".to_string());
+    }
+    if src_commit.id() == handle.repo.store().root_commit_id() {
+        return JjResult::error("Cannot squash the root commit".to_string());
+    }
+
+    let src_parent_tree = match src_commit.parent_tree(handle.repo.as_ref()) {
+        Ok(tree) => tree,
+        Err(e) => return JjResult::error(format!("Failed to read source parent tree: {}", e)),
+    };
+    let src_tree = src_commit.tree();
+    let dest_tree = dest_commit.tree();
+
+    let new_dest_tree = match dest_tree.merge(&src_parent_tree, &src_tree) {
+        Ok(tree) => tree,
+        Err(e) => {
+            return JjResult::error(format!(
+                "Failed to merge source changes into destination: {}",
+                e
+            ))
+        }
+    };
+
+    let new_description = if dest_commit.description().is_empty() {
+        src_commit.description().to_string()
+    } else {
+        dest_commit.description().to_string()
+    };
+
+    let mut tx = handle.repo.start_transaction();
+
+    if let Err(e) = tx
+        .repo_mut()
+        .rewrite_commit(&dest_commit)
+        .set_tree_id(new_dest_tree.id().clone())
+        .set_description(&new_description)
+        .write()
+    {
+        return JjResult::error(format!("Failed to rewrite destination commit: {}", e));
+    }
+
+    tx.repo_mut().record_abandoned_commit(src_commit.id().clone());
+
+    if let Err(e) = tx.repo_mut().rebase_descendants() {
+        return JjResult::error(format!("Failed to rebase descendants: {}", e));
+    }
+
+    let description = format!(
+        "squash commit {} into {}",
+        &src_commit.id().hex()[..12],
+        &dest_commit.id().hex()[..12]
+    );
+    match tx.commit(&description) {
+        Ok(new_repo) => {
+            let op_id = new_repo.operation().id().clone();
+            handle.repo = new_repo;
+            mutation_result_json(handle, op_id)
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Outcome of exporting or pushing a single bookmark's git ref.
+#[derive(Serialize)]
+struct BookmarkSyncResult {
+    name: String,
+    success: bool,
+    /// Coarse failure category for GUI branching: "non_fast_forward",
+    /// "name_collision", "conflicted", or "other". `None` on success.
+    reason: Option<String>,
+    /// Human-readable detail, always present on failure.
+    message: Option<String>,
+}
+
+/// Classify a git export/push failure into the coarse categories jj's
+/// underlying git plumbing can surface, from the failure's own message.
+fn classify_git_failure(message: &str) -> &'static str {
+    let lower = message.to_lowercase();
+    if lower.contains("conflict") {
+        "conflicted"
+    } else if lower.contains("fast-forward") || lower.contains("non-fast") {
+        "non_fast_forward"
+    } else if lower.contains("collis") || lower.contains("already exists") || lower.contains("reserved") {
+        "name_collision"
+    } else {
+        "other"
+    }
+}
+
+/// Flush local bookmarks to the backing Git repo's refs (`refs/heads/*`),
+/// like `jj git export`.
+/// Returns JjResult with a JSON array of `BookmarkSyncResult`, one per
+/// bookmark jj_lib's git export path could not update - bookmarks it did
+/// update successfully are left out, matching `GitExportStats`'s own
+/// failed-only reporting.
+#[no_mangle]
+pub extern "C" fn jj_git_export(handle: *mut RepoHandle) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let mut tx = handle.repo.start_transaction();
+
+    let stats = match jj_lib::git::export_refs(tx.repo_mut()) {
+        Ok(stats) => stats,
+        Err(e) => return JjResult::error(format!("Failed to export git refs: {}", e)),
+    };
+
+    let failures: Vec<BookmarkSyncResult> = stats
+        .failed_bookmarks
+        .iter()
+        .map(|(name, reason)| {
+            let message = reason.to_string();
+            BookmarkSyncResult {
+                name: name.as_str().to_string(),
+                success: false,
+                reason: Some(classify_git_failure(&message).to_string()),
+                message: Some(message),
+            }
+        })
+        .collect();
+
+    match tx.commit("export git refs") {
+        Ok(new_repo) => {
+            handle.repo = new_repo;
+            match serde_json::to_string(&failures) {
+                Ok(json) => JjResult::success(json),
+                Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+            }
+        }
+        Err(e) => JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+}
+
+/// Push the given bookmarks to a Git remote, like `jj git push --bookmark`.
+/// `bookmark_names` is a comma-separated list of local bookmark names.
+/// Local bookmarks are exported to the backing Git repo first, so the push
+/// reflects whatever was most recently set via `jj_set_bookmark`.
+/// Returns JjResult with a JSON array of `BookmarkSyncResult`, one entry per
+/// requested bookmark, reporting success or the reason it was rejected
+/// (moved backwards/non-fast-forward, name collision, or a conflicted
+/// target that cannot be exported) rather than a single opaque error.
+#[no_mangle]
+pub extern "C" fn jj_git_push(
+    handle: *mut RepoHandle,
+    remote_name: *const c_char,
+    bookmark_names: *const c_char,
+) -> JjResult {
+    use jj_lib::ref_name::{RefNameBuf, RemoteNameBuf};
+
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &mut *handle
+    };
+
+    let remote_str = unsafe {
+        if remote_name.is_null() {
+            return JjResult::error("null remote_name".to_string());
+        }
+        match CStr::from_ptr(remote_name).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid remote_name UTF-8: {}", e)),
+        }
+    };
+
+    let bookmark_names_str = unsafe {
+        if bookmark_names.is_null() {
+            return JjResult::error("null bookmark_names".to_string());
+        }
+        match CStr::from_ptr(bookmark_names).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid bookmark_names UTF-8: {}", e)),
+        }
+    };
+
+    let names: Vec<&str> = bookmark_names_str
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if names.is_empty() {
+        return JjResult::error("no bookmark names given".to_string());
+    }
+
+    // Export first, so the backing git repo's refs reflect whatever was most
+    // recently set locally before we try to push them.
+    let mut tx = handle.repo.start_transaction();
+    let export_stats = match jj_lib::git::export_refs(tx.repo_mut()) {
+        Ok(stats) => stats,
+        Err(e) => return JjResult::error(format!("Failed to export git refs: {}", e)),
+    };
+    let export_failure_reasons: std::collections::HashMap<String, String> = export_stats
+        .failed_bookmarks
+        .iter()
+        .map(|(name, reason)| (name.as_str().to_string(), reason.to_string()))
+        .collect();
+
+    match tx.commit("export git refs before push") {
+        Ok(new_repo) => handle.repo = new_repo,
+        Err(e) => return JjResult::error(format!("Failed to commit transaction: {}", e)),
+    }
+
+    let remote = RemoteNameBuf::from(remote_str.to_string());
+    let git_repo = match jj_lib::git::get_git_repo(handle.repo.store()) {
+        Ok(repo) => repo,
+        Err(e) => return JjResult::error(format!("Not a git-backed repo: {}", e)),
+    };
+
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        if let Some(message) = export_failure_reasons.get(name) {
+            results.push(BookmarkSyncResult {
+                name: name.to_string(),
+                success: false,
+                reason: Some(classify_git_failure(message).to_string()),
+                message: Some(message.clone()),
+            });
+            continue;
+        }
+
+        let ref_name = RefNameBuf::from(name.to_string());
+        let local_target = handle.repo.view().get_local_bookmark(&ref_name);
+        if local_target.is_absent() {
+            // A `None` new_target below means "delete this ref" - an absent
+            // bookmark (typo, already deleted, never existed) must not be
+            // silently turned into a remote ref deletion.
+            results.push(BookmarkSyncResult {
+                name: name.to_string(),
+                success: false,
+                reason: Some("other".to_string()),
+                message: Some(format!("unknown bookmark: {}", name)),
+            });
+            continue;
+        }
+        let new_target = local_target.as_normal().cloned();
+
+        let update = jj_lib::git::GitRefUpdate {
+            qualified_name: format!("refs/heads/{}", name),
+            new_target,
+        };
+
+        match jj_lib::git::push_updates(&git_repo, &remote, &[update], Default::default()) {
+            Ok(()) => results.push(BookmarkSyncResult {
+                name: name.to_string(),
+                success: true,
+                reason: None,
+                message: None,
+            }),
+            Err(e) => {
+                let message = e.to_string();
+                results.push(BookmarkSyncResult {
+                    name: name.to_string(),
+                    success: false,
+                    reason: Some(classify_git_failure(&message).to_string()),
+                    message: Some(message),
+                });
+            }
+        }
+    }
+
+    match serde_json::to_string(&results) {
+        Ok(json) => JjResult::success(json),
+        Err(e) => JjResult::error(format!("JSON serialization failed: {}", e)),
+    }
+}
+
+/// Format a commit's author timestamp as an RFC 2822 `Date:` header value,
+/// in the author's own timezone rather than UTC.
+fn format_patch_date(timestamp: &jj_lib::backend::Timestamp) -> String {
+    let epoch_seconds = timestamp.timestamp.0 / 1000;
+    let offset = chrono::FixedOffset::east_opt(timestamp.tz_offset * 60)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+
+    match chrono::DateTime::from_timestamp(epoch_seconds, 0) {
+        Some(utc) => utc
+            .with_timezone(&offset)
+            .format("%a, %d %b %Y %H:%M:%S %z")
+            .to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Render a revision as a single mbox-style patch, like
+/// `git format-patch -1 --stdout`, so a GUI caller can save, mail, or pipe
+/// the result straight into `git am`/`jj`.
+/// `revision_id` is a revset expression (commit id prefix, bookmark, etc.)
+/// resolved to exactly one commit via `resolve_single_commit`; unlike
+/// `jj_set_bookmark`'s hex-prefix walk, it is not limited to ancestors of a
+/// working copy.
+/// Returns JjResult with the patch text on success.
+#[no_mangle]
+pub extern "C" fn jj_format_patch(handle: *mut RepoHandle, revision_id: *const c_char) -> JjResult {
+    let handle = unsafe {
+        if handle.is_null() {
+            return JjResult::error("null repo handle".to_string());
+        }
+        &*handle
+    };
+
+    let revision_str = unsafe {
+        if revision_id.is_null() {
+            return JjResult::error("null revision_id".to_string());
+        }
+        match CStr::from_ptr(revision_id).to_str() {
+            Ok(s) => s,
+            Err(e) => return JjResult::error(format!("invalid revision_id UTF-8: {}", e)),
+        }
+    };
+
+    // Resolve via the revset engine rather than a hex-prefix walk from the
+    // working copy, so a commit reachable only from a bookmark/log view (not
+    // an ancestor of any `@`) can still be exported.
+    let commit = match resolve_single_commit(handle, revision_str) {
+        Ok(c) => c,
+        Err(e) => return JjResult::error(format!("Revision not found: {}", e)),
+    };
+
+    let parent_tree = match commit.parent_tree(handle.repo.as_ref()) {
+        Ok(tree) => tree,
+        Err(e) => return JjResult::error(format!("Failed to read parent tree: {}", e)),
+    };
+    let commit_tree = commit.tree();
+    let diff_body = generate_tree_diff(&handle.repo, &parent_tree, &commit_tree, 3, false, 0.0);
+
+    let signature = commit.author();
+    let description = commit.description();
+    let mut description_lines = description.lines();
+    let subject = description_lines.next().unwrap_or("");
+    let body: Vec<&str> = description_lines.collect();
+
+    let mut patch = String::new();
+    patch.push_str(&format!(
+        "From {} Mon Sep 17 00:00:00 2001\n",
+        commit.id().hex()
+    ));
+    patch.push_str(&format!("From: {} <{}>\n", signature.name, signature.email));
+    patch.push_str(&format!("Date: {}\n", format_patch_date(&signature.timestamp)));
+    patch.push_str(&format!("Subject: [PATCH] {}\n", subject));
+    patch.push('\n');
+    if !body.is_empty() {
+        patch.push_str(&body.join("\n"));
+        patch.push_str("\n\n");
+    }
+    patch.push_str(&diff_body);
+    patch.push_str(&format!("-- \njjazy {}\n", env!("CARGO_PKG_VERSION")));
+
+    JjResult::success(patch)
+}